@@ -0,0 +1,228 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ValueError;
+
+/// The name of an author, as written in the `AU` and `A1` to `A4` fields.
+///
+/// RIS writes names as `Last, First, Suffix`, such as `van der Berg, J.-P., Jr.`.
+/// Lowercase words at the start of the last name, such as `van der`, are the
+/// particle. A name without a comma, such as the name of an organisation, is kept
+/// as a whole in `literal`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Author {
+    pub family: Option<String>,
+    pub given: Option<String>,
+    pub suffix: Option<String>,
+    pub particle: Option<String>,
+    pub literal: Option<String>,
+}
+
+impl Author {
+    /// Parse a name, fails if it is empty.
+    pub fn parse(s: &str) -> Result<Self, ValueError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ValueError::new("author should not be empty"));
+        }
+        let mut parts = s.splitn(3, ',').map(str::trim);
+        let last = parts.next().unwrap_or_default();
+        let Some(given) = parts.next() else {
+            return Ok(Self {
+                literal: Some(s.to_owned()),
+                ..Self::default()
+            });
+        };
+        let (particle, family) = split_particle(last);
+        Ok(Self {
+            family: non_empty(family),
+            given: non_empty(given),
+            suffix: parts.next().and_then(non_empty),
+            particle: particle.and_then(non_empty),
+            literal: None,
+        })
+    }
+
+    /// The initials of the given names, such as `K. H.` for `Karl Heinrich` and
+    /// `J.-P.` for `Jean-Paul`.
+    ///
+    /// Given names that are initials already may be written without spaces, such as
+    /// `J.R.`, or without periods, as a word of up to three capitals such as `JR`.
+    pub fn initials(&self) -> Option<String> {
+        let initials: Vec<String> = self
+            .given
+            .as_deref()?
+            .split_whitespace()
+            .map(word_initials)
+            .filter(|initial| !initial.is_empty())
+            .collect();
+        (!initials.is_empty()).then(|| initials.join(" "))
+    }
+}
+
+impl FromStr for Author {
+    type Err = ValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Author {
+    /// Format the name in RIS `Last, First, Suffix` form.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(literal) = &self.literal {
+            return f.write_str(literal);
+        }
+        let last = [self.particle.as_deref(), self.family.as_deref()];
+        let last: Vec<&str> = last.into_iter().flatten().collect();
+        f.write_str(&last.join(" "))?;
+        match (&self.given, &self.suffix) {
+            (Some(given), Some(suffix)) => write!(f, ", {}, {}", given, suffix),
+            (Some(given), None) => write!(f, ", {}", given),
+            (None, Some(suffix)) => write!(f, ", , {}", suffix),
+            (None, None) => f.write_str(","),
+        }
+    }
+}
+
+/// The initials of a single word of the given names, see `Author::initials`.
+fn word_initials(word: &str) -> String {
+    let initial = |c: char| format!("{}.", c.to_uppercase());
+    if word.chars().count() <= 3 && word.chars().all(char::is_uppercase) {
+        return word.chars().map(initial).collect::<Vec<_>>().join(" ");
+    }
+    word.split('-')
+        .map(|part| {
+            part.split('.')
+                .filter_map(|name| name.chars().next())
+                .map(initial)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|initials| !initials.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    (!s.is_empty()).then(|| s.to_owned())
+}
+
+/// Split the leading lowercase words off a last name, as long as a word is left.
+fn split_particle(last: &str) -> (Option<&str>, &str) {
+    let mut end = 0;
+    for (idx, word) in word_starts(last) {
+        if !word.starts_with(char::is_lowercase) {
+            break;
+        }
+        end = idx + word.len();
+    }
+    let family = last[end..].trim_start();
+    if end == 0 || family.is_empty() {
+        return (None, last);
+    }
+    (Some(&last[..end]), family)
+}
+
+/// The words of `s` with their byte offsets.
+fn word_starts(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split(' ')
+        .scan(0, |offset, word| {
+            let start = *offset;
+            *offset += word.len() + 1;
+            Some((start, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn author(
+        family: Option<&str>,
+        given: Option<&str>,
+        suffix: Option<&str>,
+        particle: Option<&str>,
+        literal: Option<&str>,
+    ) -> Author {
+        Author {
+            family: family.map(str::to_owned),
+            given: given.map(str::to_owned),
+            suffix: suffix.map(str::to_owned),
+            particle: particle.map(str::to_owned),
+            literal: literal.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            (
+                "Marx, Karl",
+                author(Some("Marx"), Some("Karl"), None, None, None),
+            ),
+            (
+                "Glattauer,Daniel",
+                author(Some("Glattauer"), Some("Daniel"), None, None, None),
+            ),
+            (
+                "van der Berg, J.-P., Jr.",
+                author(
+                    Some("Berg"),
+                    Some("J.-P."),
+                    Some("Jr."),
+                    Some("van der"),
+                    None,
+                ),
+            ),
+            (
+                "de la Cruz,",
+                author(Some("Cruz"), None, None, Some("de la"), None),
+            ),
+            (
+                "van, Jan",
+                author(Some("van"), Some("Jan"), None, None, None),
+            ),
+            (
+                "World Health Organization",
+                author(None, None, None, None, Some("World Health Organization")),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Author::parse(input).unwrap(), expected, "{}", input);
+        }
+        assert!(Author::parse(" ").is_err());
+    }
+
+    #[test]
+    fn test_initials() {
+        let initials = |s| Author::parse(s).unwrap().initials();
+        assert_eq!(initials("Marx, Karl Heinrich").as_deref(), Some("K. H."));
+        assert_eq!(initials("Berg, Jean-Paul").as_deref(), Some("J.-P."));
+        assert_eq!(initials("Berg, J.-P.").as_deref(), Some("J.-P."));
+        assert_eq!(initials("Smith, J.R.").as_deref(), Some("J. R."));
+        assert_eq!(initials("Smith, JR").as_deref(), Some("J. R."));
+        assert_eq!(initials("Smith, J. R.").as_deref(), Some("J. R."));
+        assert_eq!(initials("Smith, Ed").as_deref(), Some("E."));
+        assert_eq!(initials("Öztürk, ömer").as_deref(), Some("Ö."));
+        assert_eq!(initials("Marx,"), None);
+        assert_eq!(initials("Some Organisation"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        for input in [
+            "Marx, Karl",
+            "van der Berg, J.-P., Jr.",
+            "Marx,",
+            "Marx, , Jr.",
+            "World Health Organization",
+        ] {
+            let author = Author::parse(input).unwrap();
+            assert_eq!(author.to_string(), input);
+            assert_eq!(Author::parse(&author.to_string()).unwrap(), author);
+        }
+    }
+}
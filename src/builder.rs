@@ -0,0 +1,253 @@
+use crate::parser::{BoundaryRecovery, TypeValidation, UnknownTagPolicy};
+use crate::recognizer::TagPattern;
+use crate::{ParseMode, RisParser, TagSet};
+
+/// Tags allowed by the default preset.
+pub(crate) const DEFAULT_TAGS: [&[u8; 6]; 66] = [
+    b"TY  - ", b"A1  - ", b"A2  - ", b"A3  - ", b"A4  - ", b"AB  - ", b"AD  - ", b"AN  - ",
+    b"AU  - ", b"C1  - ", b"C2  - ", b"C3  - ", b"C4  - ", b"C5  - ", b"C6  - ", b"C7  - ",
+    b"C8  - ", b"CA  - ", b"CN  - ", b"CY  - ", b"DA  - ", b"DB  - ", b"DO  - ", b"DP  - ",
+    b"ET  - ", b"EP  - ", b"ID  - ", b"IS  - ", b"J2  - ", b"JA  - ", b"JF  - ", b"JO  - ",
+    b"KW  - ", b"L1  - ", b"L2  - ", b"L3  - ", b"L4  - ", b"LA  - ", b"LB  - ", b"M1  - ",
+    b"M3  - ", b"N1  - ", b"N2  - ", b"NV  - ", b"OP  - ", b"PB  - ", b"PY  - ", b"RI  - ",
+    b"RN  - ", b"RP  - ", b"SE  - ", b"SN  - ", b"SP  - ", b"ST  - ", b"T1  - ", b"T2  - ",
+    b"T3  - ", b"TA  - ", b"TI  - ", b"TT  - ", b"UR  - ", b"VL  - ", b"Y1  - ", b"Y2  - ",
+    b"UK  - ", b"ER  - ",
+];
+
+/// Tags that the RIS specification allows to be repeated within a reference.
+const DEFAULT_LIST_TAGS: [&[u8; 6]; 12] = [
+    b"A1  - ", b"A2  - ", b"A3  - ", b"A4  - ", b"AU  - ", b"KW  - ", b"L1  - ", b"L2  - ",
+    b"L3  - ", b"L4  - ", b"N1  - ", b"UR  - ",
+];
+
+/// Builder for a `RisParser` that owns its configuration.
+///
+/// Tags are given as anything that converts to bytes, such as strings, so a parser
+/// can be configured at runtime, for example from a configuration file.
+/// `RisParserBuilder::default()` starts from the preset of `RisParser::default()`.
+#[derive(Debug, Clone)]
+pub struct RisParserBuilder {
+    start_tag: Box<[u8]>,
+    end_tag: Box<[u8]>,
+    allowed_tags: TagSet,
+    list_tags: TagSet,
+    tag_pattern: TagPattern,
+    unknown_tags: UnknownTagPolicy,
+    type_validation: TypeValidation,
+    boundary_recovery: BoundaryRecovery,
+    parallel: bool,
+    mode: ParseMode,
+    lenient_tags: bool,
+}
+
+impl RisParserBuilder {
+    /// Start with a configuration in which only the start and end tags are allowed.
+    pub fn new(start_tag: impl AsRef<[u8]>, end_tag: impl AsRef<[u8]>) -> Self {
+        Self {
+            start_tag: start_tag.as_ref().into(),
+            end_tag: end_tag.as_ref().into(),
+            allowed_tags: TagSet::new(),
+            list_tags: TagSet::new(),
+            tag_pattern: TagPattern::default(),
+            unknown_tags: UnknownTagPolicy::default(),
+            type_validation: TypeValidation::default(),
+            boundary_recovery: BoundaryRecovery::default(),
+            parallel: true,
+            mode: ParseMode::default(),
+            lenient_tags: false,
+        }
+    }
+
+    /// Set the tags that are allowed in a reference. The start and end tags are
+    /// always allowed.
+    pub fn with_allowed_tags<T: AsRef<[u8]>>(
+        mut self,
+        allowed_tags: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.allowed_tags = allowed_tags.into_iter().collect();
+        self
+    }
+
+    /// Set the tags that may occur multiple times in a reference, see
+    /// `RisParser::with_list_tags`. List tags are allowed tags as well.
+    pub fn with_list_tags<T: AsRef<[u8]>>(
+        mut self,
+        list_tags: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.list_tags = list_tags.into_iter().collect();
+        self
+    }
+
+    /// Set the shape of the tags of the format, which is RIS by default.
+    ///
+    /// The pattern tells lines with an unknown tag apart from continuation lines.
+    pub fn with_tag_pattern(mut self, tag_pattern: TagPattern) -> Self {
+        self.tag_pattern = tag_pattern;
+        self
+    }
+
+    /// Set how lines that start with a tag that is not allowed are handled.
+    pub fn with_unknown_tags(mut self, unknown_tags: UnknownTagPolicy) -> Self {
+        self.unknown_tags = unknown_tags;
+        self
+    }
+
+    /// See `RisParser::with_type_validation`.
+    pub fn with_type_validation(mut self, type_validation: TypeValidation) -> Self {
+        self.type_validation = type_validation;
+        self
+    }
+
+    /// See `RisParser::with_boundary_recovery`.
+    pub fn with_boundary_recovery(mut self, boundary_recovery: BoundaryRecovery) -> Self {
+        self.boundary_recovery = boundary_recovery;
+        self
+    }
+
+    /// Parse references in parallel with rayon, which is the default.
+    ///
+    /// The output is the same either way, sequential parsing avoids the overhead of
+    /// the thread pool for small inputs or when the caller already runs in parallel.
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// See `RisParser::with_mode`.
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// See `RisParser::with_lenient_tags`.
+    pub fn with_lenient_tags(mut self, lenient_tags: bool) -> Self {
+        self.lenient_tags = lenient_tags;
+        self
+    }
+
+    pub fn build(self) -> RisParser {
+        let mut allowed_tags = self.allowed_tags;
+        allowed_tags.extend(self.list_tags.iter());
+        allowed_tags.insert(&self.start_tag);
+        allowed_tags.insert(&self.end_tag);
+        RisParser {
+            start_tag: self.start_tag,
+            end_tag: self.end_tag,
+            allowed_tags,
+            list_tags: self.list_tags,
+            tag_pattern: self.tag_pattern,
+            unknown_tags: self.unknown_tags,
+            type_validation: self.type_validation,
+            boundary_recovery: self.boundary_recovery,
+            parallel: self.parallel,
+            mode: self.mode,
+            lenient_tags: self.lenient_tags,
+        }
+    }
+}
+
+impl Default for RisParserBuilder {
+    fn default() -> Self {
+        Self::new(b"TY  - ", b"ER  - ")
+            .with_allowed_tags(DEFAULT_TAGS)
+            .with_list_tags(DEFAULT_LIST_TAGS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_default_preset() {
+        let parser = RisParser::default();
+        assert_eq!(parser.allowed_tags.len(), DEFAULT_TAGS.len());
+        assert_eq!(parser.list_tags.len(), DEFAULT_LIST_TAGS.len());
+        assert!(parser.parallel);
+        assert_eq!(parser.unknown_tags, UnknownTagPolicy::Keep);
+    }
+
+    #[test]
+    fn test_runtime_tags() {
+        // Tags as they could be read from a configuration file.
+        let config = "PT  - ,ER  - ,TI  - ,AU  - ";
+        let tags: Vec<String> = config.split(',').map(String::from).collect();
+        let parser = RisParserBuilder::new(&tags[0], &tags[1])
+            .with_allowed_tags(&tags[2..])
+            .with_list_tags([&tags[3]])
+            .with_parallel(false)
+            .build();
+        let input = b"PT  - J\nTI  - Title\nAU  - A\nAU  - B\nER  - \n";
+        let references = parser.parse_lists(input).unwrap();
+        assert_eq!(
+            references[0]["AU  - "],
+            crate::ListOrItem::List(vec!["A", "B"])
+        );
+        assert_eq!(
+            parser.parse(input).unwrap(),
+            [HashMap::from([
+                ("PT  - ", "J"),
+                ("TI  - ", "Title"),
+                ("AU  - ", "B")
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_list_tags_are_allowed() {
+        let input = b"TY  - JOUR\nZZ  - a\nZZ  - b\nER  - \n";
+        let from_builder = RisParserBuilder::default()
+            .with_list_tags([b"ZZ  - "])
+            .build();
+        let from_parser = RisParser::default().with_list_tags([b"ZZ  - "]);
+        let expected = crate::ListOrItem::List(vec!["a", "b"]);
+        assert_eq!(
+            from_builder.parse_lists(input).unwrap()[0]["ZZ  - "],
+            expected
+        );
+        assert_eq!(
+            from_parser.parse_lists(input).unwrap()[0]["ZZ  - "],
+            expected
+        );
+        assert_eq!(from_parser.allowed_tags, from_builder.allowed_tags);
+    }
+
+    #[test]
+    fn test_unknown_tags() {
+        let input = b"TY  - JOUR\nT1  - Title\nXX  - unknown\nER  - \n";
+        let builder = RisParserBuilder::default();
+        let references = builder.clone().build().parse(input).unwrap();
+        assert_eq!(references[0]["T1  - "], "Title");
+        assert_eq!(references[0]["XX  - "], "unknown");
+
+        let parser = builder.with_unknown_tags(UnknownTagPolicy::Reject).build();
+        let err = parser.parse(input).unwrap_err();
+        assert!(matches!(err, Error::UnknownTag(..)));
+        assert_eq!(err.location().line, 3);
+        assert_eq!(err.location().tag.as_deref(), Some("XX  - "));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<RisParser>();
+
+        let parser = Arc::new(RisParser::default());
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let parser = Arc::clone(&parser);
+                thread::spawn(move || {
+                    let input = format!("TY  - JOUR\nID  - {}\nER  - \n", i);
+                    parser.parse(input.as_bytes()).unwrap()[0]["ID  - "].to_owned()
+                })
+            })
+            .collect();
+        let ids: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(ids, ["0", "1", "2", "3"]);
+    }
+}
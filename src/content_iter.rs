@@ -1,8 +1,11 @@
-use std::collections::HashSet;
-
+use crate::error::Location;
+use crate::recognizer::{recognize, TagPattern, TagVariant};
+use crate::utils::trim_line_ending;
 use crate::Error;
 use crate::PResult;
+use crate::TagSet;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TakeTagResult<'a> {
     Present(&'a [u8]),
@@ -12,82 +15,180 @@ enum TakeTagResult<'a> {
 
 /// Move the cursor to after the next newline character.
 #[derive(Debug, Clone)]
-pub struct ContentIterator<'a, 'b, const N: usize> {
-    allowed_tags: &'a HashSet<&'a [u8; N]>,
+pub struct ContentIterator<'a, 'b> {
+    allowed_tags: &'a TagSet,
+    tag_pattern: &'a TagPattern,
     text: &'b [u8],
     cursor: usize,
+    field_start: usize,
+    content_start: usize,
+    lenient_tags: bool,
+    /// Length in the input, variant and whether it is allowed, of the tag found by
+    /// the last `take_tag`.
+    tag_len: usize,
+    tag_variant: TagVariant,
+    tag_known: bool,
+    field_variant: TagVariant,
+    field_known: bool,
 }
 
-impl<'a, 'b, const N: usize> ContentIterator<'a, 'b, N> {
-    pub fn new(allowed_tags: &'a HashSet<&'a [u8; N]>, text: &'b [u8]) -> Self {
+static RIS_PATTERN: TagPattern = TagPattern::Ris;
+
+impl<'a, 'b> ContentIterator<'a, 'b> {
+    pub fn new(allowed_tags: &'a TagSet, text: &'b [u8]) -> Self {
         ContentIterator {
             allowed_tags,
+            tag_pattern: &RIS_PATTERN,
             text,
             cursor: 0,
+            field_start: 0,
+            content_start: 0,
+            lenient_tags: false,
+            tag_len: 0,
+            tag_variant: TagVariant::default(),
+            tag_known: true,
+            field_variant: TagVariant::default(),
+            field_known: true,
         }
     }
 
-    fn take_line(&mut self) -> Option<()> {
+    /// Also accept tags in other case and with other spacing, see `recognizer::recognize`.
+    pub fn with_lenient_tags(mut self, lenient_tags: bool) -> Self {
+        self.lenient_tags = lenient_tags;
+        self
+    }
+
+    /// Set the shape of the tags.
+    ///
+    /// Lines that start with a tag of this shape that is not allowed are returned
+    /// as fields with an unknown tag, never as continuation lines.
+    pub fn with_tag_pattern(mut self, tag_pattern: &'a TagPattern) -> Self {
+        self.tag_pattern = tag_pattern;
+        self
+    }
+
+    /// Whether the tag of the field that was last returned is an allowed tag.
+    pub fn field_known(&self) -> bool {
+        self.field_known
+    }
+
+    /// Variant of the tag of the field that was last returned.
+    pub fn field_variant(&self) -> TagVariant {
+        self.field_variant
+    }
+
+    /// Location of the tag of the field that was last returned, relative to the
+    /// start of the text.
+    pub fn field_location(&self) -> Location {
+        Location::start().advance(&self.text[..self.field_start])
+    }
+
+    /// Offsets of the tag and of the content of the field that was last returned.
+    pub(crate) fn field_offsets(&self) -> (usize, usize) {
+        (self.field_start, self.content_start)
+    }
+
+    fn cursor_location(&self) -> Location {
+        Location::start().advance(&self.text[..self.cursor])
+    }
+
+    /// Move the cursor to the start of the next line.
+    ///
+    /// Lines may end in `\n`, `\r\n` or `\r`. Returns the length of the line ending.
+    fn take_line(&mut self) -> Option<usize> {
         while self.cursor < self.text.len() {
-            if self.text[self.cursor] == b'\n' {
-                self.cursor += 1;
-                return Some(());
+            match self.text[self.cursor] {
+                b'\n' => {
+                    self.cursor += 1;
+                    return Some(1);
+                }
+                b'\r' => {
+                    if self.text.get(self.cursor + 1) == Some(&b'\n') {
+                        self.cursor += 2;
+                        return Some(2);
+                    }
+                    self.cursor += 1;
+                    return Some(1);
+                }
+                _ => self.cursor += 1,
             }
-            self.cursor += 1;
         }
         None
     }
 
     /// Get a tag at the current position.
     ///
-    /// The cursor is left in place.
+    /// The cursor is left in place. Tags that deviate from their canonical form are
+    /// returned in canonical form. Tags that are not allowed but match the tag
+    /// pattern are returned as well.
     fn take_tag(&mut self) -> TakeTagResult<'b> {
-        if self.text.len() < self.cursor + N {
-            return TakeTagResult::EOF;
+        let rest = &self.text[self.cursor..];
+        if let Some(tag) = self.allowed_tags.find_prefix(rest) {
+            self.tag_len = tag.len();
+            self.tag_variant = TagVariant::default();
+            self.tag_known = true;
+            return TakeTagResult::Present(tag);
+        }
+        let recognized = recognize(rest, self.lenient_tags)
+            .filter(|recognized| self.allowed_tags.contains(recognized.tag))
+            .or_else(|| self.tag_pattern.find(rest, self.lenient_tags));
+        if let Some(recognized) = recognized {
+            self.tag_len = recognized.len;
+            self.tag_variant = recognized.variant;
+            self.tag_known = self.allowed_tags.contains(recognized.tag);
+            return TakeTagResult::Present(recognized.tag);
         }
-        if self.allowed_tags.contains::<&[u8; N]>(
-            &(self.text[self.cursor..(self.cursor + N)]
-                .try_into()
-                .expect("this slice is by definition of size N")),
-        ) {
-            TakeTagResult::Present(&self.text[self.cursor..(self.cursor + N)])
+        if rest.len() < self.allowed_tags.min_width() {
+            TakeTagResult::EOF
         } else {
             TakeTagResult::NotPresent
         }
     }
 }
 
-impl<'a, 'b, const N: usize> Iterator for ContentIterator<'a, 'b, N> {
+impl<'a, 'b> Iterator for ContentIterator<'a, 'b> {
     type Item = PResult<(&'b [u8], &'b [u8])>;
 
+    /// Return the tag and the content of the next field.
+    ///
+    /// The content is a slice of the input, so content that spans multiple lines
+    /// keeps the line endings between its lines as they are. Only the line ending
+    /// before the next tag is left out.
     fn next(&mut self) -> Option<Self::Item> {
         match self.take_tag() {
             TakeTagResult::Present(tag) => {
-                self.cursor += N;
+                self.field_start = self.cursor;
+                self.field_variant = self.tag_variant;
+                self.field_known = self.tag_known;
+                self.cursor += self.tag_len;
+                self.content_start = self.cursor;
                 let content_start = self.cursor;
                 loop {
-                    if self.take_line().is_none() {
-                        return Some(Ok((tag, &self.text[content_start..])));
-                    } else {
-                        match self.take_tag() {
+                    match self.take_line() {
+                        None => return Some(Ok((tag, &self.text[content_start..]))),
+                        Some(line_ending) => match self.take_tag() {
                             TakeTagResult::Present(_) => {
-                                // We subtract 1 from the cursor to remove the final newline character.
+                                // Leave out the line ending before the next tag.
                                 return Some(Ok((
                                     tag,
-                                    &self.text[content_start..(self.cursor - 1)],
+                                    &self.text[content_start..(self.cursor - line_ending)],
                                 )));
                             }
                             TakeTagResult::NotPresent => continue,
                             TakeTagResult::EOF => {
-                                return Some(Ok((tag, &self.text[content_start..])))
+                                return Some(Ok((
+                                    tag,
+                                    trim_line_ending(&self.text[content_start..]),
+                                )))
                             }
-                        }
+                        },
                     }
                 }
             }
             TakeTagResult::EOF => None,
             TakeTagResult::NotPresent => Some(Err(Error::ParserError(
                 "line should start with a tag".into(),
+                self.cursor_location(),
             ))),
         }
     }
@@ -99,7 +200,7 @@ mod tests {
 
     #[test]
     fn test_take_line() {
-        let allowed_tags = HashSet::from([b""]);
+        let allowed_tags = TagSet::from_iter([b""]);
         let mut content_iter = ContentIterator::new(&allowed_tags, b"foo\n\nbar");
         assert!(content_iter.take_line().is_some());
         assert_eq!(content_iter.cursor, 4);
@@ -112,15 +213,19 @@ mod tests {
     #[test]
     fn test_take_tag() {
         assert_eq!(
-            ContentIterator::new(&HashSet::from([b"TY  - "]), b"TY  - foo bar").take_tag(),
+            ContentIterator::new(&TagSet::from_iter([b"TY  - "]), b"TY  - foo bar").take_tag(),
             TakeTagResult::Present(b"TY  - ")
         );
+        let allowed_tags = TagSet::from_iter([b"TY  - "]);
+        let mut content_iter = ContentIterator::new(&allowed_tags, b"QQ  - foo bar");
+        assert_eq!(content_iter.take_tag(), TakeTagResult::Present(b"QQ  - "));
+        assert!(!content_iter.tag_known);
         assert_eq!(
-            ContentIterator::new(&HashSet::from([b"TY  - "]), b"QQ  - foo bar").take_tag(),
+            ContentIterator::new(&TagSet::from_iter([b"TY  - "]), b"QQ foo bar").take_tag(),
             TakeTagResult::NotPresent
         );
         assert_eq!(
-            ContentIterator::new(&HashSet::from([b"TY  - "]), b"TY").take_tag(),
+            ContentIterator::new(&TagSet::from_iter([b"TY  - "]), b"TY").take_tag(),
             TakeTagResult::EOF
         );
     }
@@ -132,11 +237,9 @@ ID  - 12345
 A2  - Glattauer, Daniel
 UR  - http://example_url.com
 ER  - ";
-        let allowed_tags = HashSet::from([b"TY  - ", b"A2  - ", b"ID  - ", b"UR  - ", b"ER  - "]);
-        let mut content_iter = ContentIterator::new(
-            &allowed_tags,
-            ref_bytes,
-        );
+        let allowed_tags =
+            TagSet::from_iter([b"TY  - ", b"A2  - ", b"ID  - ", b"UR  - ", b"ER  - "]);
+        let mut content_iter = ContentIterator::new(&allowed_tags, ref_bytes);
         assert_eq!(
             content_iter.next(),
             Some(Ok(("TY  - ".as_bytes(), "JOUR".as_bytes())))
@@ -163,4 +266,137 @@ ER  - ";
         );
         assert_eq!(content_iter.next(), None);
     }
+
+    #[test]
+    fn test_error_location() {
+        let allowed_tags = TagSet::from_iter([b"TY  - ", b"ER  - "]);
+        let mut content_iter = ContentIterator::new(&allowed_tags, b"QQ foo");
+        let err = content_iter.next().unwrap().unwrap_err();
+        assert_eq!(err.location(), &Location::start());
+    }
+
+    #[test]
+    fn test_field_location() {
+        let allowed_tags = TagSet::from_iter([b"TY  - ", b"ID  - ", b"ER  - "]);
+        let mut content_iter =
+            ContentIterator::new(&allowed_tags, b"TY  - JOUR\nID  - 1\n2\nER  - ");
+        content_iter.next();
+        assert_eq!(content_iter.field_location(), Location::start());
+        content_iter.next();
+        content_iter.next();
+        let location = content_iter.field_location();
+        assert_eq!(
+            (location.offset, location.line, location.column),
+            (21, 4, 1)
+        );
+    }
+
+    #[test]
+    fn test_take_line_endings() {
+        let allowed_tags = TagSet::from_iter([b""]);
+        let mut content_iter = ContentIterator::new(&allowed_tags, b"a\r\nb\rc\n\r\n");
+        assert_eq!(content_iter.take_line(), Some(2));
+        assert_eq!(content_iter.cursor, 3);
+        assert_eq!(content_iter.take_line(), Some(1));
+        assert_eq!(content_iter.cursor, 5);
+        assert_eq!(content_iter.take_line(), Some(1));
+        assert_eq!(content_iter.cursor, 7);
+        assert_eq!(content_iter.take_line(), Some(2));
+        assert_eq!(content_iter.take_line(), None);
+    }
+
+    #[test]
+    fn test_next_mixed_line_endings() {
+        let allowed_tags =
+            TagSet::from_iter([b"TY  - ", b"AB  - ", b"ID  - ", b"UR  - ", b"ER  - "]);
+        let mut content_iter = ContentIterator::new(
+            &allowed_tags,
+            b"TY  - JOUR\r\nID  - 12345\rAB  - first\r\nsecond\n\
+UR  - http://example.com\r\nER  - \r\n",
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"TY  - ".as_slice(), b"JOUR".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"ID  - ".as_slice(), b"12345".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"AB  - ".as_slice(), b"first\r\nsecond".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"UR  - ".as_slice(), b"http://example.com".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"ER  - ".as_slice(), b"".as_slice())))
+        );
+        assert_eq!(content_iter.next(), None);
+    }
+
+    #[test]
+    fn test_next_tag_variants() {
+        let allowed_tags = TagSet::from_iter([b"TY  - ", b"T1  - ", b"KW  - ", b"ER  - "]);
+        let ref_bytes = b"ty  - JOUR\nT1\t-\tTitle\nKW  - a\nER  -";
+
+        let mut content_iter = ContentIterator::new(&allowed_tags, ref_bytes);
+        assert_eq!(
+            content_iter.next(),
+            Some(Err(Error::ParserError(
+                "line should start with a tag".into(),
+                Location::start()
+            )))
+        );
+
+        let mut content_iter =
+            ContentIterator::new(&allowed_tags, ref_bytes).with_lenient_tags(true);
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"TY  - ".as_slice(), b"JOUR".as_slice())))
+        );
+        assert!(content_iter.field_variant().lowercase);
+        assert!(!content_iter.field_variant().irregular_spacing);
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"T1  - ".as_slice(), b"Title".as_slice())))
+        );
+        assert!(content_iter.field_variant().irregular_spacing);
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"KW  - ".as_slice(), b"a".as_slice())))
+        );
+        assert!(content_iter.field_variant().is_canonical());
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"ER  - ".as_slice(), b"".as_slice())))
+        );
+        assert!(content_iter.field_variant().missing_trailing_space);
+        assert_eq!(content_iter.next(), None);
+    }
+
+    #[test]
+    fn test_stripped_tag_is_not_continuation() {
+        let allowed_tags = TagSet::from_iter([b"TY  - ", b"N1  - ", b"ER  - "]);
+        let mut content_iter =
+            ContentIterator::new(&allowed_tags, b"TY  - JOUR\nN1  -\r\nN1  - note\r\nER  -");
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"TY  - ".as_slice(), b"JOUR".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"N1  - ".as_slice(), b"".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"N1  - ".as_slice(), b"note".as_slice())))
+        );
+        assert_eq!(
+            content_iter.next(),
+            Some(Ok((b"ER  - ".as_slice(), b"".as_slice())))
+        );
+    }
 }
@@ -0,0 +1,290 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::content_iter::ContentIterator;
+use crate::error::Location;
+use crate::ref_iter::LineKind;
+use crate::utils::trim_line_endings;
+use crate::{PResult, RisParser};
+
+/// A lossless syntax tree of RIS input, created by `RisParser::parse_cst`.
+///
+/// The tree holds every byte of the input: the records, split into fields, and the
+/// trivia in between them, such as numbering lines, blank lines and a byte order
+/// mark. `to_bytes` reproduces the input exactly. After fields are edited only
+/// those fields differ from the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyntaxTree<'b> {
+    pub nodes: Vec<Node<'b>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node<'b> {
+    /// Bytes outside of the records.
+    Trivia(Cow<'b, [u8]>),
+    Record(RecordNode<'b>),
+}
+
+/// The fields of a record, from the start tag up to and including the end tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordNode<'b> {
+    pub fields: Vec<FieldNode<'b>>,
+}
+
+/// A field of a record, with the bytes around its content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldNode<'b> {
+    /// The tag in its canonical form, as used to look up fields.
+    pub tag: Cow<'b, [u8]>,
+    /// The tag as it is written in the input.
+    pub raw_tag: Cow<'b, [u8]>,
+    /// The content, including continuation lines and their line endings.
+    pub content: Cow<'b, [u8]>,
+    /// The line ending after the content, empty at the end of the input.
+    pub line_ending: Cow<'b, [u8]>,
+}
+
+impl<'b> SyntaxTree<'b> {
+    pub(crate) fn parse(parser: &RisParser, input: &'b [u8]) -> PResult<Self> {
+        let mut splitter = parser.line_splitter();
+        let mut nodes = Vec::new();
+        let mut trivia_start = 0;
+        let mut record_start = 0;
+        let mut offset = 0;
+        for line in lines(input) {
+            let line_start = offset;
+            offset += line.len();
+            let without_bom = splitter.strip_bom(line);
+            match splitter.push_line(without_bom) {
+                LineKind::Outside | LineKind::Inside => {}
+                LineKind::Start => {
+                    record_start = offset - without_bom.len();
+                    if record_start > trivia_start {
+                        let trivia = &input[trivia_start..record_start];
+                        nodes.push(Node::Trivia(trivia.into()));
+                    }
+                }
+                LineKind::End(location) => {
+                    let record = &input[record_start..offset];
+                    nodes.push(Node::Record(RecordNode::parse(parser, record, &location)?));
+                    trivia_start = offset;
+                }
+                LineKind::Restart(location) => {
+                    let record = &input[record_start..line_start];
+                    nodes.push(Node::Record(RecordNode::parse(parser, record, &location)?));
+                    record_start = line_start;
+                    trivia_start = line_start;
+                }
+                LineKind::Recovered(location, blank) => {
+                    let record = &input[record_start..(line_start - blank)];
+                    nodes.push(Node::Record(RecordNode::parse(parser, record, &location)?));
+                    trivia_start = line_start - blank;
+                }
+            }
+        }
+        if let Some(location) = splitter.finish()? {
+            let record = &input[record_start..];
+            nodes.push(Node::Record(RecordNode::parse(parser, record, &location)?));
+            trivia_start = input.len();
+        }
+        if input.len() > trivia_start {
+            nodes.push(Node::Trivia(input[trivia_start..].into()));
+        }
+        Ok(Self { nodes })
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &RecordNode<'b>> {
+        self.nodes.iter().filter_map(|node| match node {
+            Node::Record(record) => Some(record),
+            Node::Trivia(_) => None,
+        })
+    }
+
+    pub fn records_mut(&mut self) -> impl Iterator<Item = &mut RecordNode<'b>> {
+        self.nodes.iter_mut().filter_map(|node| match node {
+            Node::Record(record) => Some(record),
+            Node::Trivia(_) => None,
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        for node in &self.nodes {
+            match node {
+                Node::Trivia(trivia) => writer.write_all(trivia)?,
+                Node::Record(record) => record.write_to(writer)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)
+            .expect("writing to a Vec does not fail");
+        bytes
+    }
+}
+
+impl<'b> RecordNode<'b> {
+    fn parse(parser: &RisParser, text: &'b [u8], location: &Location) -> PResult<Self> {
+        // A record without end tag may end in the line endings of empty lines,
+        // they are kept with the last field.
+        let body = trim_line_endings(text);
+        let mut iter = ContentIterator::new(&parser.allowed_tags, body)
+            .with_tag_pattern(&parser.tag_pattern)
+            .with_lenient_tags(parser.lenient_tags);
+        let mut fields: Vec<FieldNode> = Vec::new();
+        let mut content_end = 0;
+        while let Some(res) = iter.next() {
+            let (tag, content) = res.map_err(|e| e.relative_to(location))?;
+            let (tag_start, content_start) = iter.field_offsets();
+            if let Some(previous) = fields.last_mut() {
+                previous.line_ending = body[content_end..tag_start].into();
+            }
+            content_end = content_start + content.len();
+            fields.push(FieldNode {
+                tag: tag.into(),
+                raw_tag: body[tag_start..content_start].into(),
+                content: content.into(),
+                line_ending: Cow::Borrowed(&[]),
+            });
+        }
+        if let Some(last) = fields.last_mut() {
+            last.line_ending = text[content_end..].into();
+        }
+        Ok(Self { fields })
+    }
+
+    /// The first field with the given canonical tag.
+    pub fn field(&self, tag: &[u8]) -> Option<&FieldNode<'b>> {
+        self.fields.iter().find(|field| *field.tag == *tag)
+    }
+
+    /// The first field with the given canonical tag, to edit it.
+    pub fn field_mut(&mut self, tag: &[u8]) -> Option<&mut FieldNode<'b>> {
+        self.fields.iter_mut().find(|field| *field.tag == *tag)
+    }
+
+    /// Insert a field at `index`, with the line ending of the start tag line.
+    pub fn insert_field(&mut self, index: usize, tag: &[u8], content: &[u8]) {
+        let line_ending = self
+            .fields
+            .first()
+            .map(|field| field.line_ending.clone())
+            .filter(|line_ending| !line_ending.is_empty())
+            .unwrap_or(Cow::Borrowed(b"\n"));
+        self.fields.insert(
+            index,
+            FieldNode {
+                tag: tag.to_vec().into(),
+                raw_tag: tag.to_vec().into(),
+                content: content.to_vec().into(),
+                line_ending,
+            },
+        );
+    }
+
+    pub fn remove_field(&mut self, index: usize) -> FieldNode<'b> {
+        self.fields.remove(index)
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        for field in &self.fields {
+            writer.write_all(&field.raw_tag)?;
+            writer.write_all(&field.content)?;
+            writer.write_all(&field.line_ending)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'b> FieldNode<'b> {
+    /// Replace the content, which is written as is.
+    pub fn set_content(&mut self, content: impl Into<Vec<u8>>) {
+        self.content = Cow::Owned(content.into());
+    }
+}
+
+/// The lines of `text`, including their line endings.
+fn lines(text: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = match rest.iter().position(|c| *c == b'\n' || *c == b'\r') {
+            Some(idx) if rest[idx..].starts_with(b"\r\n") => idx + 2,
+            Some(idx) => idx + 1,
+            None => rest.len(),
+        };
+        let (line, remainder) = rest.split_at(end);
+        rest = remainder;
+        Some(line)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    const INPUT: &[u8] = b"\xef\xbb\xbfProvider: Somewhere\r\n\r\n1.\r\nTY  - JOUR\r\n\
+T1  - Title\r\nAB  - First line\r\nsecond line\r\nty  - lenient\r\nER  - \r\n\r\n\
+2.\nTY  - BOOK\nID  - 2\nER  -\ntrailing junk";
+
+    #[test]
+    fn test_lossless() {
+        let parser = RisParser::default().with_lenient_tags(true);
+        let tree = parser.parse_cst(INPUT).unwrap();
+        assert_eq!(tree.to_bytes(), INPUT);
+        assert_eq!(tree.nodes.len(), 5);
+        assert_eq!(
+            tree.nodes[0],
+            Node::Trivia(Cow::Borrowed(
+                b"\xef\xbb\xbfProvider: Somewhere\r\n\r\n1.\r\n"
+            ))
+        );
+        assert_eq!(tree.nodes[4], Node::Trivia(Cow::Borrowed(b"trailing junk")));
+
+        let records: Vec<_> = tree.records().collect();
+        assert_eq!(records.len(), 2);
+        let abstract_field = records[0].field(b"AB  - ").unwrap();
+        assert_eq!(&*abstract_field.content, b"First line\r\nsecond line");
+        assert_eq!(&*abstract_field.line_ending, b"\r\n");
+        let lenient = &records[0].fields[3];
+        assert_eq!(&*lenient.tag, b"TY  - ");
+        assert_eq!(&*lenient.raw_tag, b"ty  - ");
+        assert_eq!(&*records[1].fields[2].raw_tag, b"ER  -");
+        assert_eq!(&*records[1].fields[2].line_ending, b"\n");
+    }
+
+    #[test]
+    fn test_edit() {
+        let parser = RisParser::default().with_lenient_tags(true);
+        let mut tree = parser.parse_cst(INPUT).unwrap();
+        let record = tree.records_mut().next().unwrap();
+        record
+            .field_mut(b"T1  - ")
+            .unwrap()
+            .set_content("New title");
+        record.insert_field(2, b"PY  - ", b"2024");
+        let record = tree.records_mut().nth(1).unwrap();
+        assert_eq!(&*record.remove_field(1).content, b"2");
+        assert_eq!(record.fields.len(), 2);
+
+        let expected = std::str::from_utf8(&INPUT[3..])
+            .unwrap()
+            .replace("T1  - Title\r\n", "T1  - New title\r\nPY  - 2024\r\n")
+            .replace("ID  - 2\n", "");
+        assert_eq!(tree.to_bytes(), [&INPUT[..3], expected.as_bytes()].concat());
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let parser = RisParser::default();
+        let err = parser.parse_cst(b"TY  - JOUR\nT1  - Title\n").unwrap_err();
+        assert!(matches!(err, Error::EOF(..)));
+        let tree = parser.parse_cst(b"").unwrap();
+        assert!(tree.nodes.is_empty());
+    }
+}
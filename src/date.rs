@@ -0,0 +1,312 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ValueError;
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// A date as written in the `PY`, `Y1`, `Y2` and `DA` fields.
+///
+/// The RIS form is `YYYY/MM/DD/other`, in which every part may be empty, such as
+/// `2014//` or `2006/03//Spring`. Dates that do not have a year, month or day,
+/// such as free text, only have `other`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RisDate {
+    pub year: Option<i32>,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+    /// Other information, such as a season.
+    pub other: Option<String>,
+}
+
+impl RisDate {
+    /// Parse a date.
+    ///
+    /// Besides the RIS form, and its variants without trailing slashes, ISO 8601
+    /// dates such as `2014-05-12` and dates such as `2014 May 12` are accepted. Text
+    /// after the year, month or day, such as `Winter` in `2014 Winter`, and input
+    /// that does not start with a year are kept as `other`. Fails on empty input and
+    /// on months and days that do not exist.
+    pub fn parse(s: &str) -> Result<Self, ValueError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(date_error("date should not be empty", s));
+        }
+        let date = parse_slashes(s)
+            .or_else(|| parse_iso(s))
+            .or_else(|| parse_year_text(s))
+            .unwrap_or_else(|| Self {
+                other: Some(s.to_owned()),
+                ..Self::default()
+            });
+        date.validate(s)?;
+        Ok(date)
+    }
+
+    /// The date in ISO 8601 form, such as `2014-05-12` or `2014-05`.
+    ///
+    /// `None` if there is no year, or if the year does not have four digits. A
+    /// day without a month, and `other`, are left out.
+    pub fn to_iso8601(&self) -> Option<String> {
+        let year = self.year.filter(|year| (0..=9999).contains(year))?;
+        Some(match (self.month, self.day) {
+            (Some(month), Some(day)) => format!("{:04}-{:02}-{:02}", year, month, day),
+            (Some(month), None) => format!("{:04}-{:02}", year, month),
+            (None, _) => format!("{:04}", year),
+        })
+    }
+
+    fn validate(&self, s: &str) -> Result<(), ValueError> {
+        if let Some(month) = self.month {
+            if !(1..=12).contains(&month) {
+                return Err(date_error("month should be from 1 to 12", s));
+            }
+        }
+        if let Some(day) = self.day {
+            if day < 1 || day > days_in_month(self.year, self.month) {
+                return Err(date_error("day does not exist", s));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RisDate {
+    type Err = ValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for RisDate {
+    /// Format the date in RIS form. A date with only `other` is written as is.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.year.is_none() && self.month.is_none() && self.day.is_none() {
+            return f.write_str(self.other.as_deref().unwrap_or_default());
+        }
+        if let Some(year) = self.year {
+            write!(f, "{:04}", year)?;
+        }
+        f.write_str("/")?;
+        if let Some(month) = self.month {
+            write!(f, "{:02}", month)?;
+        }
+        f.write_str("/")?;
+        if let Some(day) = self.day {
+            write!(f, "{:02}", day)?;
+        }
+        f.write_str("/")?;
+        f.write_str(self.other.as_deref().unwrap_or_default())
+    }
+}
+
+fn date_error(message: &str, s: &str) -> ValueError {
+    ValueError::new(format!("{}: {:?}", message, s))
+}
+
+/// Parse an optional number, `Some(None)` if `part` is empty and `None` if it is
+/// not a number.
+fn parse_part<T: FromStr>(part: &str) -> Option<Option<T>> {
+    if part.is_empty() {
+        return Some(None);
+    }
+    if !part.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    part.parse().ok().map(Some)
+}
+
+/// `YYYY/MM/DD/other`, with any number of trailing parts left out.
+fn parse_slashes(s: &str) -> Option<RisDate> {
+    let mut parts = s.splitn(4, '/');
+    let date = RisDate {
+        year: parse_part(parts.next()?)?,
+        month: parse_part(parts.next().unwrap_or_default())?,
+        day: parse_part(parts.next().unwrap_or_default())?,
+        other: parts
+            .next()
+            .filter(|other| !other.is_empty())
+            .map(str::to_owned),
+    };
+    Some(date)
+}
+
+/// `YYYY-MM-DD` or `YYYY-MM`.
+fn parse_iso(s: &str) -> Option<RisDate> {
+    let mut parts = s.split('-');
+    let year = parse_part(parts.next()?)??;
+    let month = parse_part(parts.next()?)??;
+    let day = match parts.next() {
+        Some(day) => Some(parse_part(day)??),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(RisDate {
+        year: Some(year),
+        month: Some(month),
+        day,
+        other: None,
+    })
+}
+
+/// A year followed by text, such as `2014 Winter` or `2014/Spring`. If the text
+/// starts with a month name, optionally followed by a day, as in `2014 May 12`, they
+/// are parsed, the rest of the text is kept as `other`.
+fn parse_year_text(s: &str) -> Option<RisDate> {
+    let (year, rest) = s.split_once(|c: char| c.is_whitespace() || c == '/')?;
+    let mut date = RisDate {
+        year: Some(parse_part(year)??),
+        ..RisDate::default()
+    };
+    let mut rest = rest.trim_start();
+    let (word, after) = split_word(rest);
+    if let Some(month) = month_number(word) {
+        date.month = Some(month);
+        rest = after;
+        let (word, after) = split_word(rest);
+        if let Some(Some(day)) = parse_part(word) {
+            date.day = Some(day);
+            rest = after;
+        }
+    }
+    date.other = Some(rest.to_owned()).filter(|other| !other.is_empty());
+    Some(date)
+}
+
+/// Split off the first word of `s`, and the text after it without leading
+/// whitespace.
+fn split_word(s: &str) -> (&str, &str) {
+    let (word, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+    (word, rest.trim_start())
+}
+
+/// The number of an English month name, which is either written in full or
+/// abbreviated to three letters, such as `Mar` or `Mar.`. `Sept` is accepted too.
+fn month_number(name: &str) -> Option<u8> {
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    let name = if name == "sept" { "sep" } else { &name };
+    let month = MONTHS
+        .iter()
+        .position(|month| *month == name || (name.len() == 3 && month.starts_with(name)))?;
+    Some(month as u8 + 1)
+}
+
+fn days_in_month(year: Option<i32>, month: Option<u8>) -> u8 {
+    match month {
+        Some(4 | 6 | 9 | 11) => 30,
+        Some(2) => match year {
+            Some(year) if !(year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)) => 28,
+            _ => 29,
+        },
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: Option<i32>, month: Option<u8>, day: Option<u8>, other: Option<&str>) -> RisDate {
+        RisDate {
+            year,
+            month,
+            day,
+            other: other.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            ("2014", date(Some(2014), None, None, None)),
+            ("2014//", date(Some(2014), None, None, None)),
+            ("2014///", date(Some(2014), None, None, None)),
+            ("2014/05/12/", date(Some(2014), Some(5), Some(12), None)),
+            ("2014/5/12", date(Some(2014), Some(5), Some(12), None)),
+            (
+                "2006/03//Spring",
+                date(Some(2006), Some(3), None, Some("Spring")),
+            ),
+            ("///Spring", date(None, None, None, Some("Spring"))),
+            ("2014-05-12", date(Some(2014), Some(5), Some(12), None)),
+            ("2014-05", date(Some(2014), Some(5), None, None)),
+            ("2014 May 12", date(Some(2014), Some(5), Some(12), None)),
+            ("2014 Sept.", date(Some(2014), Some(9), None, None)),
+            ("Spring 2006", date(None, None, None, Some("Spring 2006"))),
+            ("2014/Spring", date(Some(2014), None, None, Some("Spring"))),
+            ("2014 Winter", date(Some(2014), None, None, Some("Winter"))),
+            (
+                "2014 Mar-Apr",
+                date(Some(2014), None, None, Some("Mar-Apr")),
+            ),
+            (
+                "2014 Marathon",
+                date(Some(2014), None, None, Some("Marathon")),
+            ),
+            ("2014 March", date(Some(2014), Some(3), None, None)),
+            (
+                "2014 Dec 24 Christmas Eve",
+                date(Some(2014), Some(12), Some(24), Some("Christmas Eve")),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(RisDate::parse(input).unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for input in [
+            "",
+            "  ",
+            "2014/13//",
+            "2014/02/30/",
+            "2013-02-29",
+            "2014 Jan 32",
+        ] {
+            assert!(RisDate::parse(input).is_err(), "{}", input);
+        }
+        assert!(RisDate::parse("2012/02/29/").is_ok());
+    }
+
+    #[test]
+    fn test_format() {
+        for (input, expected) in [
+            ("2014//", "2014///"),
+            ("2014-5-2", "2014/05/02/"),
+            ("2006/03//Spring", "2006/03//Spring"),
+            ("Spring 2006", "Spring 2006"),
+        ] {
+            let date = RisDate::parse(input).unwrap();
+            assert_eq!(date.to_string(), expected);
+            assert_eq!(RisDate::parse(expected).unwrap(), date);
+        }
+    }
+
+    #[test]
+    fn test_iso8601() {
+        let iso = |s| RisDate::parse(s).unwrap().to_iso8601();
+        assert_eq!(iso("2014/05/12/").as_deref(), Some("2014-05-12"));
+        assert_eq!(iso("2006/03//Spring").as_deref(), Some("2006-03"));
+        assert_eq!(iso("2014///").as_deref(), Some("2014"));
+        assert_eq!(iso("2014//12/").as_deref(), Some("2014"));
+        assert_eq!(iso("Spring 2006"), None);
+        assert_eq!(iso("12345"), None);
+    }
+}
@@ -0,0 +1,318 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::error::Location;
+use crate::{Error, PResult};
+
+/// Character encoding of the input.
+///
+/// The parser works on UTF-8, input in other encodings is transcoded with
+/// `Encoding::decode` first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+    Latin1,
+}
+
+/// Number of bytes looked at by the heuristic in `Encoding::detect`.
+const SAMPLE_LEN: usize = 4096;
+
+/// Characters of the bytes 0x80 to 0x9F in Windows-1252. The bytes that are not
+/// assigned map to the C1 control character of the same value, like browsers do.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Windows1252 => "Windows-1252",
+            Self::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// The encoding indicated by the byte order mark at the start of the input.
+    pub fn from_bom(input: &[u8]) -> Option<Self> {
+        match input {
+            [0xef, 0xbb, 0xbf, ..] => Some(Self::Utf8),
+            [0xff, 0xfe, ..] => Some(Self::Utf16Le),
+            [0xfe, 0xff, ..] => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Guess the encoding of the input.
+    ///
+    /// A byte order mark decides the encoding. Without one, input that consists
+    /// mostly of ASCII characters padded with zero bytes is taken to be UTF-16,
+    /// valid UTF-8 to be UTF-8 and anything else to be Windows-1252, which is what
+    /// older EndNote and Reference Manager exports use.
+    pub fn detect(input: &[u8]) -> Self {
+        if let Some(encoding) = Self::from_bom(input) {
+            return encoding;
+        }
+        let sample = &input[..input.len().min(SAMPLE_LEN)];
+        let pairs = sample.len() / 2;
+        let zeros_at = |parity| {
+            sample
+                .iter()
+                .skip(parity)
+                .step_by(2)
+                .filter(|c| **c == 0)
+                .count()
+        };
+        let (even_zeros, odd_zeros) = (zeros_at(0), zeros_at(1));
+        if pairs > 0 && odd_zeros * 2 > pairs && even_zeros * 4 < odd_zeros {
+            return Self::Utf16Le;
+        }
+        if pairs > 0 && even_zeros * 2 > pairs && odd_zeros * 4 < even_zeros {
+            return Self::Utf16Be;
+        }
+        match std::str::from_utf8(input) {
+            Ok(_) => Self::Utf8,
+            // Input that is cut off in the middle of a character is still UTF-8.
+            Err(e) if e.error_len().is_none() => Self::Utf8,
+            Err(_) => Self::Windows1252,
+        }
+    }
+
+    /// Transcode the input to UTF-8.
+    ///
+    /// Input that needs no changes is borrowed. A byte order mark is kept, as
+    /// U+FEFF, and is skipped by the parser. UTF-8 input is not validated unless
+    /// `lossy` is set, in which case invalid bytes are replaced by U+FFFD, so the
+    /// parser can report invalid UTF-8 with its location. For UTF-16, `lossy` also
+    /// replaces unpaired surrogates instead of failing on them.
+    pub fn decode(self, input: &[u8], lossy: bool) -> PResult<Cow<'_, [u8]>> {
+        match self {
+            Self::Utf8 if lossy => Ok(match String::from_utf8_lossy(input) {
+                Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+                Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+            }),
+            Self::Utf8 => Ok(Cow::Borrowed(input)),
+            Self::Latin1 => Ok(decode_single_byte(input, char::from)),
+            Self::Windows1252 => Ok(decode_single_byte(input, |c| match c {
+                0x80..=0x9f => WINDOWS_1252[usize::from(c - 0x80)],
+                _ => char::from(c),
+            })),
+            Self::Utf16Le => decode_utf16(self, input, lossy, u16::from_le_bytes),
+            Self::Utf16Be => decode_utf16(self, input, lossy, u16::from_be_bytes),
+        }
+    }
+}
+
+/// Transcodes input that is read line by line, for `RisParser::parse_reader` and
+/// `RisPushParser`.
+///
+/// Without an explicit encoding, the encoding is detected once, from the first line
+/// that is not plain ASCII, and is then used for the rest of the input. Plain ASCII
+/// reads the same in UTF-8 and in the single-byte encodings. UTF-16 cannot be split
+/// into lines on single bytes and is rejected.
+#[derive(Debug, Clone)]
+pub(crate) struct LineDecoder {
+    pub(crate) encoding: Option<Encoding>,
+    pub(crate) lossy: bool,
+}
+
+impl LineDecoder {
+    pub(crate) fn new(encoding: Option<Encoding>, lossy: bool) -> Self {
+        Self { encoding, lossy }
+    }
+
+    /// Register the next line of the input, including its line ending.
+    pub(crate) fn push_line(&mut self, line: &[u8]) -> PResult<()> {
+        if self.encoding.is_none() && line.iter().any(|c| !c.is_ascii() || *c == 0) {
+            self.encoding = Some(Encoding::detect(line));
+        }
+        match self.encoding {
+            Some(encoding @ (Encoding::Utf16Le | Encoding::Utf16Be)) => Err(Error::ParserError(
+                format!("{} input cannot be read line by line", encoding),
+                Location::default(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Transcode a reference to UTF-8, see `Encoding::decode`.
+    pub(crate) fn decode<'b>(&self, input: &'b [u8]) -> PResult<Cow<'b, [u8]>> {
+        self.encoding
+            .unwrap_or(Encoding::Utf8)
+            .decode(input, self.lossy)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn decode_single_byte(input: &[u8], to_char: impl Fn(u8) -> char) -> Cow<'_, [u8]> {
+    if input.is_ascii() {
+        return Cow::Borrowed(input);
+    }
+    let text: String = input.iter().map(|c| to_char(*c)).collect();
+    Cow::Owned(text.into_bytes())
+}
+
+fn decode_utf16(
+    encoding: Encoding,
+    input: &[u8],
+    lossy: bool,
+    to_unit: fn([u8; 2]) -> u16,
+) -> PResult<Cow<'static, [u8]>> {
+    let invalid = |offset: usize| {
+        Error::ParserError(
+            format!("invalid {} at byte {}", encoding, offset),
+            Location::default(),
+        )
+    };
+    let units = input.chunks_exact(2).map(|c| to_unit([c[0], c[1]]));
+    let mut text = String::with_capacity(input.len() / 2);
+    let mut offset = 0;
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => {
+                offset += c.len_utf16() * 2;
+                text.push(c);
+            }
+            Err(_) if lossy => {
+                offset += 2;
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            Err(_) => return Err(invalid(offset)),
+        }
+    }
+    if input.len() % 2 == 1 {
+        if !lossy {
+            return Err(invalid(input.len() - 1));
+        }
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    Ok(Cow::Owned(text.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|c| match little_endian {
+                true => c.to_le_bytes(),
+                false => c.to_be_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        let text = "TY  - JOUR\r\nT1  - Caf\u{e9}\r\nER  - \r\n";
+        assert_eq!(Encoding::detect(text.as_bytes()), Encoding::Utf8);
+        assert_eq!(
+            Encoding::detect(b"TY  - JOUR\r\nT1  - Caf\xe9\r\n"),
+            Encoding::Windows1252
+        );
+        assert_eq!(Encoding::detect(&utf16(text, true)), Encoding::Utf16Le);
+        assert_eq!(Encoding::detect(&utf16(text, false)), Encoding::Utf16Be);
+        assert_eq!(
+            Encoding::detect(&utf16("\u{feff}x", true)),
+            Encoding::Utf16Le
+        );
+        assert_eq!(
+            Encoding::detect(&utf16("\u{feff}x", false)),
+            Encoding::Utf16Be
+        );
+        assert_eq!(Encoding::detect(b"\xef\xbb\xbfCaf\xe9"), Encoding::Utf8);
+        assert_eq!(
+            Encoding::detect("Caf\u{e9}".as_bytes().split_last().unwrap().1),
+            Encoding::Utf8
+        );
+        assert_eq!(Encoding::detect(b""), Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_decode_single_byte() {
+        let input = b"T1  - Caf\xe9 \x80\x93 \x81";
+        assert!(matches!(
+            Encoding::Latin1.decode(b"ASCII", false),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert_eq!(
+            Encoding::Windows1252.decode(input, false).unwrap(),
+            "T1  - Caf\u{e9} \u{20ac}\u{201c} \u{81}".as_bytes()
+        );
+        assert_eq!(
+            Encoding::Latin1.decode(input, false).unwrap(),
+            "T1  - Caf\u{e9} \u{80}\u{93} \u{81}".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let text = "\u{feff}T1  - Caf\u{e9} \u{1f600}";
+        assert_eq!(
+            Encoding::Utf16Le.decode(&utf16(text, true), false).unwrap(),
+            text.as_bytes()
+        );
+        assert_eq!(
+            Encoding::Utf16Be
+                .decode(&utf16(text, false), false)
+                .unwrap(),
+            text.as_bytes()
+        );
+
+        // An unpaired surrogate and a trailing odd byte.
+        let mut input = utf16("ab", true);
+        input.extend([0x00, 0xd8, b'c', 0x00, b'd']);
+        let err = Encoding::Utf16Le.decode(&input, false).unwrap_err();
+        assert_eq!(err.to_string(), "invalid UTF-16LE at byte 4");
+        assert_eq!(
+            Encoding::Utf16Le.decode(&input, true).unwrap(),
+            "ab\u{fffd}c\u{fffd}".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_line_decoder() {
+        let mut decoder = LineDecoder::new(None, false);
+        decoder.push_line(b"TY  - JOUR\n").unwrap();
+        assert_eq!(decoder.encoding, None);
+        decoder.push_line(b"T1  - Caf\xe9\n").unwrap();
+        assert_eq!(decoder.encoding, Some(Encoding::Windows1252));
+        // Later lines do not change the detected encoding.
+        decoder.push_line("T1  - Caf\u{e9}\n".as_bytes()).unwrap();
+        assert_eq!(decoder.decode(b"\x93").unwrap(), "\u{201c}".as_bytes());
+
+        let mut decoder = LineDecoder::new(None, false);
+        let err = decoder
+            .push_line(&utf16("\u{feff}TY  - JOUR\r", true))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("UTF-16LE input cannot be read line by line"));
+        let mut decoder = LineDecoder::new(Some(Encoding::Utf16Be), false);
+        assert!(decoder.push_line(b"TY  - JOUR\n").is_err());
+    }
+
+    #[test]
+    fn test_decode_lossy_utf8() {
+        assert!(matches!(
+            Encoding::Utf8.decode(b"\xff", false),
+            Ok(Cow::Borrowed(b"\xff"))
+        ));
+        assert_eq!(
+            Encoding::Utf8.decode(b"Caf\xe9", true).unwrap(),
+            "Caf\u{fffd}".as_bytes()
+        );
+    }
+}
@@ -1,22 +1,276 @@
 use pyo3::exceptions::PyException;
 use pyo3::PyErr;
 use std::fmt;
+use std::io;
+use std::ops::Deref;
+use std::str::Utf8Error;
+use std::sync::Arc;
+
+use crate::recognizer::TagVariant;
+use crate::ReferenceType;
+
+/// Where in the input an error occurred.
+///
+/// Lines and columns start at 1, columns are counted in bytes. A location with
+/// line 0 has not been resolved to a position in the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset from the start of the input.
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    /// Index of the reference in the input, starting at 0.
+    pub reference: Option<usize>,
+    /// Tag of the field that was being parsed.
+    pub tag: Option<String>,
+}
+
+impl Location {
+    /// Location of the first byte of the input.
+    pub fn start() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+            reference: None,
+            tag: None,
+        }
+    }
+
+    /// Location of the byte directly after `text`, if `text` starts at this location.
+    pub(crate) fn advance(&self, text: &[u8]) -> Self {
+        let mut location = self.clone();
+        for (idx, c) in text.iter().enumerate() {
+            location.offset += 1;
+            // A \r\n line ending counts as a single line break, on the \n.
+            let line_break = *c == b'\n' || (*c == b'\r' && text.get(idx + 1) != Some(&b'\n'));
+            if line_break {
+                location.line += 1;
+                location.column = 1;
+            } else {
+                location.column += 1;
+            }
+        }
+        location
+    }
+
+    /// Translate a location relative to the start of a reference to a location in the
+    /// full input, given the location `base` at which the reference starts.
+    pub(crate) fn relative_to(mut self, base: &Location) -> Self {
+        self.offset += base.offset;
+        if self.line == 1 {
+            self.column += base.column - 1;
+        }
+        self.line += base.line - 1;
+        if self.reference.is_none() {
+            self.reference = base.reference;
+        }
+        if self.tag.is_none() {
+            self.tag.clone_from(&base.tag);
+        }
+        self
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (byte {})",
+            self.line, self.column, self.offset
+        )?;
+        if let Some(reference) = self.reference {
+            write!(f, ", reference {}", reference)?;
+        }
+        if let Some(tag) = &self.tag {
+            write!(f, ", tag {:?}", tag)?;
+        }
+        Ok(())
+    }
+}
+
+/// An I/O error, shared so that `Error` can be cloned.
+///
+/// Two I/O errors are considered equal if they have the same kind and message.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<io::Error>);
+
+impl Deref for IoError {
+    type Target = io::Error;
+
+    fn deref(&self) -> &io::Error {
+        &self.0
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind() && self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for IoError {}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
-    EOF,
-    UnknownTag(String),
-    ParserError(String),
+    EOF(Location),
+    UnknownTag(String, Location),
+    ParserError(String, Location),
+    InvalidUtf8(Utf8Error, Location),
+    Io(IoError, Location),
+}
+
+impl Error {
+    pub fn location(&self) -> &Location {
+        match self {
+            Self::EOF(location)
+            | Self::UnknownTag(_, location)
+            | Self::ParserError(_, location)
+            | Self::InvalidUtf8(_, location)
+            | Self::Io(_, location) => location,
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut Location {
+        match self {
+            Self::EOF(location)
+            | Self::UnknownTag(_, location)
+            | Self::ParserError(_, location)
+            | Self::InvalidUtf8(_, location)
+            | Self::Io(_, location) => location,
+        }
+    }
+
+    /// Set the location of an error that was raised without knowing its position.
+    pub(crate) fn at(mut self, location: Location) -> Self {
+        let current = self.location_mut();
+        if current.line == 0 {
+            let tag = current.tag.take();
+            *current = location;
+            if tag.is_some() {
+                current.tag = tag;
+            }
+        }
+        self
+    }
+
+    /// Translate the location of the error, see `Location::relative_to`.
+    pub(crate) fn relative_to(mut self, base: &Location) -> Self {
+        let location = self.location_mut();
+        *location = std::mem::take(location).relative_to(base);
+        self
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let message = match self {
-            Self::EOF => "End of input reached during parsing".to_owned(),
-            Self::UnknownTag(s) => format!("Unknown tag encountered: {}", &s),
-            Self::ParserError(s) => s.to_string(),
+            Self::EOF(_) => "End of input reached during parsing".to_owned(),
+            Self::UnknownTag(s, _) => format!("Unknown tag encountered: {}", &s),
+            Self::ParserError(s, _) => s.to_string(),
+            Self::InvalidUtf8(e, _) => format!("invalid utf-8: {}", e),
+            Self::Io(e, _) => format!("I/O error: {}", **e),
         };
-        write!(f, "{}", message)
+        let location = self.location();
+        if location.line == 0 {
+            write!(f, "{}", message)
+        } else {
+            write!(f, "{} at {}", message, location)
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidUtf8(e, _) => Some(e),
+            Self::Io(e, _) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(IoError(Arc::new(value)), Location::default())
+    }
+}
+
+/// An error from parsing a single value, such as a date or a name, that is not
+/// read from a position in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueError(String);
+
+impl ValueError {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// Something unusual in the input that did not prevent it from being parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A tag was written in a non-canonical form, the location holds the canonical tag.
+    TagVariant(TagVariant, Location),
+    /// A field with a tag that is not allowed was left out, the location holds the
+    /// tag.
+    UnknownTag(Location),
+    /// A reference has a type that is not in the RIS specification, the location
+    /// holds the start tag.
+    UnknownType(ReferenceType, Location),
+}
+
+impl Warning {
+    pub fn location(&self) -> &Location {
+        match self {
+            Self::TagVariant(_, location)
+            | Self::UnknownTag(location)
+            | Self::UnknownType(_, location) => location,
+        }
+    }
+
+    /// Translate the location of the warning, see `Location::relative_to`.
+    pub(crate) fn relative_to(self, base: &Location) -> Self {
+        match self {
+            Self::TagVariant(variant, location) => {
+                Self::TagVariant(variant, location.relative_to(base))
+            }
+            Self::UnknownTag(location) => Self::UnknownTag(location.relative_to(base)),
+            Self::UnknownType(reference_type, location) => {
+                Self::UnknownType(reference_type, location.relative_to(base))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TagVariant(variant, location) => {
+                write!(f, "Non-canonical tag ({:?}) at {}", variant, location)
+            }
+            Self::UnknownTag(location) => write!(f, "Unknown tag left out at {}", location),
+            Self::UnknownType(reference_type, location) => {
+                write!(
+                    f,
+                    "Unknown reference type {:?} at {}",
+                    reference_type.code(),
+                    location
+                )
+            }
+        }
     }
 }
 
@@ -24,4 +278,4 @@ impl std::convert::From<Error> for PyErr {
     fn from(value: Error) -> Self {
         PyException::new_err(value.to_string())
     }
-}
\ No newline at end of file
+}
@@ -1,11 +1,22 @@
-use std::collections::HashSet;
-
+use crate::error::Location;
+use crate::Error;
 use crate::PResult;
+use crate::TagSet;
 
-pub trait Handler<'a, 'b, S, T, const N: usize> {
-    fn start_tag(&self) -> &'a [u8; N];
-    fn end_tag(&self) -> &'a [u8; N];
-    fn allowed_tags(&self) -> &'a HashSet<&'a [u8; N]>;
+pub trait Handler<'a, 'b, S, T> {
+    fn start_tag(&self) -> &'a [u8];
+    fn end_tag(&self) -> &'a [u8];
+    fn allowed_tags(&self) -> &'a TagSet;
     fn handle(&mut self, tag: &'b [u8], content: S) -> PResult<()>;
+
+    /// Handle a field whose tag is not in the allowed tags, which is only called
+    /// with `UnknownTagPolicy::Keep`. Handlers reject such fields by default.
+    fn handle_unknown(&mut self, _tag: &'b [u8], _content: S) -> PResult<()> {
+        Err(Error::UnknownTag(
+            "tag should be in allowed tags".into(),
+            Location::default(),
+        ))
+    }
+
     fn finish(self) -> T;
 }
@@ -1,24 +1,22 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
+use crate::error::Location;
 use crate::utils::parse_utf8;
 use crate::Error;
 use crate::Handler;
 use crate::PResult;
+use crate::TagSet;
 
 #[derive(Debug, Clone)]
-pub struct HashMapHandler<'a, 'b, T, const N: usize> {
-    start_tag: &'a [u8; N],
-    end_tag: &'a [u8; N],
-    allowed_tags: &'a HashSet<&'a [u8; N]>,
+pub struct HashMapHandler<'a, 'b, T> {
+    start_tag: &'a [u8],
+    end_tag: &'a [u8],
+    allowed_tags: &'a TagSet,
     state: HashMap<&'b str, T>,
 }
 
-impl<'a, 'b, T, const N: usize> HashMapHandler<'a, 'b, T, N> {
-    pub fn new(
-        start_tag: &'a [u8; N],
-        end_tag: &'a [u8; N],
-        allowed_tags: &'a HashSet<&'a [u8; N]>,
-    ) -> Self {
+impl<'a, 'b, T> HashMapHandler<'a, 'b, T> {
+    pub fn new(start_tag: &'a [u8], end_tag: &'a [u8], allowed_tags: &'a TagSet) -> Self {
         Self {
             start_tag,
             end_tag,
@@ -28,16 +26,13 @@ impl<'a, 'b, T, const N: usize> HashMapHandler<'a, 'b, T, N> {
     }
 }
 
-impl<'a, 'b, T, const N: usize> Handler<'a, 'b, T, HashMap<&'b str, T>, N>
-    for HashMapHandler<'a, 'b, T, N>
-{
+impl<'a, 'b, T> Handler<'a, 'b, T, HashMap<&'b str, T>> for HashMapHandler<'a, 'b, T> {
     fn handle(&mut self, tag: &'b [u8], content: T) -> PResult<()> {
-        if tag.len() != N {
-            return Err(Error::UnknownTag(format!("tag should have length {}", N)));
-        }
-        let tag: &[u8; N] = tag.try_into().unwrap();
         if !self.allowed_tags.contains(tag) {
-            return Err(Error::UnknownTag("tag should be in allowed tags".into()));
+            return Err(Error::UnknownTag(
+                "tag should be in allowed tags".into(),
+                Location::default(),
+            ));
         }
         if tag != self.end_tag {
             self.state.insert(parse_utf8(tag)?, content);
@@ -45,20 +40,25 @@ impl<'a, 'b, T, const N: usize> Handler<'a, 'b, T, HashMap<&'b str, T>, N>
         Ok(())
     }
 
+    fn handle_unknown(&mut self, tag: &'b [u8], content: T) -> PResult<()> {
+        self.state.insert(parse_utf8(tag)?, content);
+        Ok(())
+    }
+
     fn finish(mut self) -> HashMap<&'b str, T> {
         self.state.shrink_to_fit();
         self.state
     }
 
-    fn start_tag(&self) -> &'a [u8; N] {
+    fn start_tag(&self) -> &'a [u8] {
         self.start_tag
     }
 
-    fn end_tag(&self) -> &'a [u8; N] {
+    fn end_tag(&self) -> &'a [u8] {
         self.end_tag
     }
 
-    fn allowed_tags(&self) -> &'a HashSet<&'a [u8; N]> {
+    fn allowed_tags(&self) -> &'a TagSet {
         self.allowed_tags
     }
 }
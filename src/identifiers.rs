@@ -0,0 +1,372 @@
+use std::fmt;
+
+use crate::Tag;
+
+/// An identifier of a reference, in canonical form.
+///
+/// Identifiers are found with `Identifier::extract`, or on a parsed reference with
+/// `Reference::identifiers`. ISSNs and ISBNs with a wrong check digit are reported
+/// as `InvalidIssn` and `InvalidIsbn`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Identifier {
+    /// A DOI in lowercase, without resolver prefix, such as `10.1371/journal.pone.0001`.
+    Doi(String),
+    /// An ISSN with a hyphen, such as `1932-6203`.
+    Issn(String),
+    /// An ISBN-10 or ISBN-13 without hyphens, such as `9780306406157`.
+    Isbn(String),
+    /// A PubMed ID without leading zeros.
+    Pmid(String),
+    /// An arXiv ID without `arXiv:` prefix, such as `2101.00001v2` or
+    /// `hep-th/9901001`.
+    Arxiv(String),
+    /// An ISSN with a wrong check digit, in the form of `Issn`.
+    InvalidIssn(String),
+    /// An ISBN with a wrong check digit, in the form of `Isbn`.
+    InvalidIsbn(String),
+}
+
+impl Identifier {
+    /// The identifiers in the content of a field with the given tag.
+    ///
+    /// DOIs are taken from `DO`, `UR` and `L3`, ISSNs and ISBNs from `SN`, PubMed
+    /// IDs from `AN` and `M1` when prefixed with `PMID`, and arXiv IDs from `DO`,
+    /// `UR`, `L3`, `AN` and `M1`.
+    pub fn extract(tag: &Tag, content: &str) -> Vec<Self> {
+        Self::extract_from(tag, content, false)
+    }
+
+    /// Same as `extract`, for a field of a reference from PubMed or MEDLINE, in
+    /// which `AN` holds the PubMed ID without prefix.
+    pub fn extract_pubmed(tag: &Tag, content: &str) -> Vec<Self> {
+        Self::extract_from(tag, content, true)
+    }
+
+    fn extract_from(tag: &Tag, content: &str, pubmed: bool) -> Vec<Self> {
+        let mut identifiers = Vec::new();
+        match tag {
+            Tag::Doi | Tag::Url | Tag::RelatedRecords => {
+                identifiers.extend(Self::parse_doi(content));
+                identifiers.extend(Self::parse_arxiv(content));
+            }
+            Tag::IsbnIssn => identifiers.extend(
+                content
+                    .split(|c: char| c.is_whitespace() || ";,():".contains(c))
+                    .filter_map(|token| {
+                        Self::parse_issn(token).or_else(|| Self::parse_isbn(token))
+                    }),
+            ),
+            Tag::AccessionNumber | Tag::Miscellaneous1 => {
+                let content = content.trim();
+                let pmid = match content.get(..4) {
+                    Some(prefix) if prefix.eq_ignore_ascii_case("pmid") => {
+                        Some(content[4..].trim_start_matches([':', ' ']))
+                    }
+                    _ if pubmed && *tag == Tag::AccessionNumber => Some(content),
+                    _ => None,
+                };
+                identifiers.extend(pmid.and_then(Self::parse_pmid));
+                identifiers.extend(Self::parse_arxiv(content));
+            }
+            _ => {}
+        }
+        identifiers
+    }
+
+    /// Find a DOI in `s`, which may be a bare DOI, a `doi:` reference or a URL
+    /// such as `https://doi.org/10.1000/xyz`.
+    pub fn parse_doi(s: &str) -> Option<Self> {
+        let mut search = 0;
+        while let Some(idx) = s[search..].find("10.").map(|idx| idx + search) {
+            search = idx + 3;
+            if s[..idx].ends_with(|c: char| c.is_ascii_alphanumeric()) {
+                continue;
+            }
+            let rest = &s[idx + 3..];
+            let registrant = rest
+                .bytes()
+                .take_while(|c| c.is_ascii_digit() || *c == b'.');
+            let registrant_len = registrant.count();
+            if registrant_len < 4 || rest.as_bytes().get(registrant_len) != Some(&b'/') {
+                continue;
+            }
+            let doi: &str = s[idx..]
+                .split(char::is_whitespace)
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(['.', ',', ';', ')', ']', '"', '\'']);
+            if doi.len() > registrant_len + 4 {
+                return Some(Self::Doi(doi.to_lowercase()));
+            }
+        }
+        None
+    }
+
+    /// Parse an ISSN, with or without its hyphen. `InvalidIssn` if the check digit
+    /// is wrong.
+    pub fn parse_issn(s: &str) -> Option<Self> {
+        let compact = compact(s);
+        if !compact.is_ascii() || compact.len() != 8 {
+            return None;
+        }
+        let valid = check_digits(&compact, 8)?;
+        let issn = format!("{}-{}", &compact[..4], &compact[4..]);
+        Some(if valid {
+            Self::Issn(issn)
+        } else {
+            Self::InvalidIssn(issn)
+        })
+    }
+
+    /// Parse an ISBN-10 or ISBN-13, with or without hyphens. `InvalidIsbn` if the
+    /// check digit is wrong.
+    pub fn parse_isbn(s: &str) -> Option<Self> {
+        let compact = compact(s);
+        if !compact.is_ascii() {
+            return None;
+        }
+        let valid = match compact.len() {
+            10 => check_digits(&compact, 10)?,
+            13 if compact.bytes().all(|c| c.is_ascii_digit())
+                && (compact.starts_with("978") || compact.starts_with("979")) =>
+            {
+                let sum: u32 = compact
+                    .bytes()
+                    .zip([1, 3].into_iter().cycle())
+                    .map(|(c, weight)| (c - b'0') as u32 * weight)
+                    .sum();
+                sum.is_multiple_of(10)
+            }
+            _ => return None,
+        };
+        Some(if valid {
+            Self::Isbn(compact)
+        } else {
+            Self::InvalidIsbn(compact)
+        })
+    }
+
+    /// Parse a PubMed ID, a number of at most 9 digits.
+    pub fn parse_pmid(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let valid = !s.is_empty() && s.len() <= 9 && s.bytes().all(|c| c.is_ascii_digit());
+        let pmid = s.trim_start_matches('0');
+        (valid && !pmid.is_empty()).then(|| Self::Pmid(pmid.to_owned()))
+    }
+
+    /// Find an arXiv ID in `s`, which may be prefixed with `arXiv:`, be an
+    /// `arxiv.org` URL or be an arXiv DOI.
+    pub fn parse_arxiv(s: &str) -> Option<Self> {
+        let lower = s.to_ascii_lowercase();
+        ["arxiv.org/abs/", "arxiv.org/pdf/", "arxiv:", "arxiv."]
+            .iter()
+            .filter_map(|prefix| lower.find(prefix).map(|idx| &s[idx + prefix.len()..]))
+            .find_map(|rest| {
+                let id = rest
+                    .trim_start()
+                    .split(|c: char| c.is_whitespace() || c == '?' || c == '#')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches(['.', ',', ';', ')']);
+                let id = id.strip_suffix(".pdf").unwrap_or(id);
+                is_arxiv_id(id).then(|| Self::Arxiv(id.to_owned()))
+            })
+    }
+
+    /// Whether the identifier has a correct check digit, which is only checked for
+    /// ISSNs and ISBNs.
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, Self::InvalidIssn(_) | Self::InvalidIsbn(_))
+    }
+
+    /// The identifier in canonical form.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Doi(id)
+            | Self::Issn(id)
+            | Self::Isbn(id)
+            | Self::Pmid(id)
+            | Self::Arxiv(id)
+            | Self::InvalidIssn(id)
+            | Self::InvalidIsbn(id) => id,
+        }
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `s` without hyphens and with an uppercase check digit.
+fn compact(s: &str) -> String {
+    s.trim()
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Validate the check digit of an ASCII ISSN or ISBN-10, of which the digits are
+/// weighted from `weight` down and the check digit may be `X`. `None` if `s` is
+/// not made up of digits and a check digit.
+fn check_digits(s: &str, weight: u32) -> Option<bool> {
+    let (digits, check_digit) = s.split_at(s.len() - 1);
+    let well_formed = digits.bytes().all(|c| c.is_ascii_digit())
+        && (check_digit == "X" || check_digit.bytes().all(|c| c.is_ascii_digit()));
+    if !well_formed {
+        return None;
+    }
+    let sum: u32 = digits
+        .bytes()
+        .zip((2..=weight).rev())
+        .map(|(c, weight)| (c - b'0') as u32 * weight)
+        .sum();
+    Some(match ((11 - sum % 11) % 11, check_digit) {
+        (10, "X") => true,
+        (expected, digit) => digit.parse() == Ok(expected),
+    })
+}
+
+/// Whether `id` is a new-style arXiv ID, such as `2101.00001v2`, or an old-style
+/// one, such as `hep-th/9901001`.
+fn is_arxiv_id(id: &str) -> bool {
+    let is_digits = |s: &str, len: std::ops::RangeInclusive<usize>| {
+        len.contains(&s.len()) && s.bytes().all(|c| c.is_ascii_digit())
+    };
+    let id = match id.rsplit_once('v') {
+        Some((id, version)) if is_digits(version, 1..=3) => id,
+        _ => id,
+    };
+    match id.split_once('/') {
+        Some((archive, number)) => {
+            let archive = archive.split('.').next().unwrap_or_default();
+            !archive.is_empty()
+                && archive.bytes().all(|c| c.is_ascii_lowercase() || c == b'-')
+                && is_digits(number, 7..=7)
+        }
+        None => match id.split_once('.') {
+            Some((month, number)) => is_digits(month, 4..=4) && is_digits(number, 4..=5),
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doi() {
+        for input in [
+            "10.1371/journal.pone.0001",
+            "doi:10.1371/JOURNAL.PONE.0001",
+            "https://doi.org/10.1371/journal.pone.0001",
+            "http://dx.doi.org/10.1371/journal.pone.0001.",
+            "(see 10.1371/journal.pone.0001)",
+        ] {
+            assert_eq!(
+                Identifier::parse_doi(input),
+                Some(Identifier::Doi("10.1371/journal.pone.0001".into())),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(Identifier::parse_doi("version 10.1 of 12/2020"), None);
+        assert_eq!(Identifier::parse_doi("x10.1000/abc"), None);
+        assert_eq!(Identifier::parse_doi("10.1000/"), None);
+    }
+
+    #[test]
+    fn test_issn() {
+        assert_eq!(
+            Identifier::parse_issn("1932-6203"),
+            Some(Identifier::Issn("1932-6203".into()))
+        );
+        assert_eq!(
+            Identifier::parse_issn("2434561x"),
+            Some(Identifier::Issn("2434-561X".into()))
+        );
+        assert_eq!(
+            Identifier::parse_issn("1932-6208"),
+            Some(Identifier::InvalidIssn("1932-6208".into()))
+        );
+        assert!(!Identifier::parse_issn("1932-6208").unwrap().is_valid());
+        assert_eq!(Identifier::parse_issn("1932-62X3"), None);
+        assert_eq!(Identifier::parse_issn("1932-620"), None);
+    }
+
+    #[test]
+    fn test_isbn() {
+        assert_eq!(
+            Identifier::parse_isbn("978-0-306-40615-7"),
+            Some(Identifier::Isbn("9780306406157".into()))
+        );
+        assert_eq!(
+            Identifier::parse_isbn("0-8044-2957-X"),
+            Some(Identifier::Isbn("080442957X".into()))
+        );
+        assert_eq!(
+            Identifier::parse_isbn("978-0-306-40615-8"),
+            Some(Identifier::InvalidIsbn("9780306406158".into()))
+        );
+        assert_eq!(
+            Identifier::parse_isbn("0-8044-2957-1"),
+            Some(Identifier::InvalidIsbn("0804429571".into()))
+        );
+        assert_eq!(Identifier::parse_isbn("1234567890123"), None);
+    }
+
+    #[test]
+    fn test_arxiv() {
+        for (input, expected) in [
+            ("arXiv:2101.00001v2", "2101.00001v2"),
+            ("https://arxiv.org/abs/2101.00001", "2101.00001"),
+            ("https://arxiv.org/pdf/2101.00001v1.pdf", "2101.00001v1"),
+            ("10.48550/arXiv.2101.00001", "2101.00001"),
+            ("arXiv: hep-th/9901001", "hep-th/9901001"),
+            ("arxiv:math.GT/0309136", "math.GT/0309136"),
+        ] {
+            assert_eq!(
+                Identifier::parse_arxiv(input),
+                Some(Identifier::Arxiv(expected.into())),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(Identifier::parse_arxiv("arXiv:2101"), None);
+        assert_eq!(Identifier::parse_arxiv("2101.00001"), None);
+    }
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(
+            Identifier::extract(&Tag::IsbnIssn, "1932-6203 (Electronic); 978-0-306-40615-7"),
+            [
+                Identifier::Issn("1932-6203".into()),
+                Identifier::Isbn("9780306406157".into())
+            ]
+        );
+        assert_eq!(
+            Identifier::extract(&Tag::IsbnIssn, "1932-6208"),
+            [Identifier::InvalidIssn("1932-6208".into())]
+        );
+        assert!(Identifier::extract(&Tag::AccessionNumber, "618012345").is_empty());
+        assert_eq!(
+            Identifier::extract(&Tag::AccessionNumber, "PMID:618012345"),
+            [Identifier::Pmid("618012345".into())]
+        );
+        assert_eq!(
+            Identifier::extract_pubmed(&Tag::AccessionNumber, "00012345"),
+            [Identifier::Pmid("12345".into())]
+        );
+        assert!(Identifier::extract_pubmed(&Tag::Miscellaneous1, "12345").is_empty());
+        assert_eq!(
+            Identifier::extract(&Tag::Miscellaneous1, "PMID: 12345"),
+            [Identifier::Pmid("12345".into())]
+        );
+        assert!(Identifier::extract(&Tag::Miscellaneous1, "12345").is_empty());
+        assert!(Identifier::extract(&Tag::Title, "10.1000/xyz").is_empty());
+    }
+}
@@ -1,16 +1,57 @@
+mod author;
+mod builder;
 mod content_iter;
+mod cst;
+mod date;
+mod encoding;
 mod error;
 mod handler;
 mod hashmap_handler;
+mod identifiers;
 mod list_handler;
+mod mapped_file;
+mod pages;
 mod parser;
+mod preamble;
+mod push_parser;
 mod python_bindings;
+mod reader;
+mod recognizer;
+mod record_handler;
 mod ref_iter;
+mod reference_handler;
+mod reference_type;
+mod tag;
+mod tag_handler;
+mod tag_set;
 mod utils;
+mod writer;
 
 pub type PResult<T> = Result<T, Error>;
 
-pub use error::Error;
+pub use author::Author;
+pub use builder::RisParserBuilder;
+pub use cst::{FieldNode, Node, RecordNode, SyntaxTree};
+pub use date::RisDate;
+pub use encoding::Encoding;
+pub use error::{Error, IoError, Location, ValueError, Warning};
 pub use handler::Handler;
-pub use parser::RisParser;
-pub use ref_iter::ReferenceIterator;
\ No newline at end of file
+pub use identifiers::Identifier;
+pub use list_handler::ListOrItem;
+pub use mapped_file::ParsedFile;
+pub use pages::PageRange;
+pub use parser::{
+    BoundaryRecovery, ExtraField, ParseMode, ParseReport, ReferenceInfo, RisParser, TypeValidation,
+    UnknownTagPolicy,
+};
+pub use preamble::Preamble;
+pub use push_parser::RisPushParser;
+pub use reader::ReferenceReader;
+pub use recognizer::{TagPattern, TagVariant};
+pub use record_handler::Record;
+pub use ref_iter::ReferenceIterator;
+pub use reference_handler::Reference;
+pub use reference_type::ReferenceType;
+pub use tag::Tag;
+pub use tag_set::TagSet;
+pub use writer::{LineEnding, RisWriter};
@@ -1,11 +1,12 @@
 use std::collections::HashMap;
-use std::collections::HashSet;
 
+use crate::error::Location;
 use crate::hashmap_handler::HashMapHandler;
 use crate::utils::parse_utf8;
 use crate::Error;
 use crate::Handler;
 use crate::PResult;
+use crate::TagSet;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ListOrItem<T> {
@@ -14,14 +15,14 @@ pub enum ListOrItem<T> {
 }
 
 #[derive(Debug, Clone)]
-pub struct ListHandler<'a, 'b, T, const N: usize> {
-    handler: HashMapHandler<'a, 'b, T, N>,
-    list_tags: &'a HashSet<&'a [u8; N]>,
+pub struct ListHandler<'a, 'b, T> {
+    handler: HashMapHandler<'a, 'b, T>,
+    list_tags: &'a TagSet,
     lists: HashMap<&'b str, Vec<T>>,
 }
 
-impl<'a, 'b, T, const N: usize> ListHandler<'a, 'b, T, N> {
-    pub fn new(handler: HashMapHandler<'a, 'b, T, N>, list_tags: &'a HashSet<&'a [u8; N]>) -> Self {
+impl<'a, 'b, T> ListHandler<'a, 'b, T> {
+    pub fn new(handler: HashMapHandler<'a, 'b, T>, list_tags: &'a TagSet) -> Self {
         Self {
             handler,
             list_tags,
@@ -30,42 +31,41 @@ impl<'a, 'b, T, const N: usize> ListHandler<'a, 'b, T, N> {
     }
 }
 
-impl<'a, 'b, T, const N: usize> Handler<'a, 'b, T, HashMap<&'b str, ListOrItem<T>>, N>
-    for ListHandler<'a, 'b, T, N>
-{
-    fn start_tag(&self) -> &'a [u8; N] {
+impl<'a, 'b, T> Handler<'a, 'b, T, HashMap<&'b str, ListOrItem<T>>> for ListHandler<'a, 'b, T> {
+    fn start_tag(&self) -> &'a [u8] {
         self.handler.start_tag()
     }
 
-    fn end_tag(&self) -> &'a [u8; N] {
+    fn end_tag(&self) -> &'a [u8] {
         self.handler.end_tag()
     }
 
-    fn allowed_tags(&self) -> &'a HashSet<&'a [u8; N]> {
+    fn allowed_tags(&self) -> &'a TagSet {
         self.handler.allowed_tags()
     }
 
     fn handle(&mut self, tag: &'b [u8], content: T) -> PResult<()> {
-        if tag.len() != N {
-            return Err(Error::UnknownTag(format!("tag should have length {}", N)));
-        }
-        let tag: &[u8; N] = tag.try_into().unwrap();
         if self.list_tags.contains(tag) {
-            let utf_tag = parse_utf8(tag)?;
-            match self.lists.get_mut(utf_tag) {
-                Some(vec) => {
-                    vec.push(content);
-                }
-                None => {
-                    self.lists.insert(&utf_tag, vec![content]);
-                }
+            if !self.allowed_tags().contains(tag) {
+                return Err(Error::UnknownTag(
+                    "tag should be in allowed tags".into(),
+                    Location::default(),
+                ));
             }
+            self.lists
+                .entry(parse_utf8(tag)?)
+                .or_default()
+                .push(content);
             Ok(())
         } else {
             self.handler.handle(tag, content)
         }
     }
 
+    fn handle_unknown(&mut self, tag: &'b [u8], content: T) -> PResult<()> {
+        self.handler.handle_unknown(tag, content)
+    }
+
     fn finish(self) -> HashMap<&'b str, ListOrItem<T>> {
         self.handler
             .finish()
@@ -86,9 +86,9 @@ mod tests {
 
     #[test]
     fn test_list_handler() {
-        let allowed_tags = HashSet::from([b"FOO", b"BAR", b"STA", b"END"]);
+        let allowed_tags = TagSet::from_iter([b"FOO", b"BAR", b"STA", b"END"]);
         let base_handler = HashMapHandler::new(b"STA", b"END", &allowed_tags);
-        let list_tags = HashSet::from([b"FOO"]);
+        let list_tags = TagSet::from_iter([b"FOO"]);
         let mut handler = ListHandler::new(base_handler, &list_tags);
 
         handler.handle(b"STA", "0").unwrap();
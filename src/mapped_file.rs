@@ -0,0 +1,169 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::slice;
+
+use memmap2::Mmap;
+
+use crate::{Encoding, PResult, RisParser};
+
+/// References parsed from a memory-mapped file, created by `RisParser::parse_file`.
+///
+/// The references borrow from the mapping, or from the transcoded text if the file
+/// is not UTF-8, which is owned by this guard and is freed when the guard is dropped.
+#[derive(Debug)]
+pub struct ParsedFile {
+    // Declared before `_data`, so the references are dropped before their text.
+    references: Vec<HashMap<&'static str, &'static str>>,
+    _data: Data,
+}
+
+#[derive(Debug)]
+enum Data {
+    /// An empty file, which cannot be mapped on every platform.
+    Empty,
+    Mapped(Mmap),
+    Decoded(Vec<u8>),
+}
+
+impl ParsedFile {
+    /// # Safety
+    ///
+    /// The file must not be modified while the guard is alive, see
+    /// `RisParser::parse_file`.
+    pub(crate) unsafe fn open(
+        parser: &RisParser,
+        path: impl AsRef<Path>,
+        encoding: Option<Encoding>,
+        lossy: bool,
+    ) -> PResult<Self> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Ok(Self {
+                references: parser.parse(&[])?,
+                _data: Data::Empty,
+            });
+        }
+        // SAFETY: the file is not modified while it is mapped, which the caller
+        // guarantees.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let encoding = encoding.unwrap_or_else(|| Encoding::detect(&mmap));
+        let data = match encoding.decode(&mmap, lossy)? {
+            Cow::Borrowed(_) => Data::Mapped(mmap),
+            Cow::Owned(decoded) => Data::Decoded(decoded),
+        };
+        let text = match &data {
+            Data::Empty => &[][..],
+            Data::Mapped(mmap) => &mmap[..],
+            Data::Decoded(decoded) => &decoded[..],
+        };
+        // SAFETY: neither the mapped memory nor the heap allocation of the decoded
+        // text moves when the guard is moved, and both live until the guard is
+        // dropped. The references are only handed out with the lifetime of the
+        // guard, and are dropped before the text.
+        let text: &'static [u8] = unsafe { slice::from_raw_parts(text.as_ptr(), text.len()) };
+        Ok(Self {
+            references: parser.parse(text)?,
+            _data: data,
+        })
+    }
+
+    /// The parsed references, borrowing from the mapped file.
+    pub fn references(&self) -> &[HashMap<&str, &str>] {
+        &self.references
+    }
+
+    pub fn len(&self) -> usize {
+        self.references.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+}
+
+impl<'s> IntoIterator for &'s ParsedFile {
+    type Item = &'s HashMap<&'s str, &'s str>;
+    type IntoIter = slice::Iter<'s, HashMap<&'s str, &'s str>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.references().iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::io::Write;
+
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("ris-{}-{}", std::process::id(), name));
+        File::create(&path).unwrap().write_all(contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let contents = b"\xef\xbb\xbfTY  - JOUR\nT1  - Mapped\nER  - \nTY  - BOOK\nER  - \n";
+        let path = temp_file("mapped.ris", contents);
+        let parser = RisParser::default();
+        // SAFETY: the temporary files of the tests are not modified while mapped.
+        let parsed = unsafe { parser.parse_file(&path) }.unwrap();
+        assert_eq!(parsed.references(), parser.parse(contents).unwrap());
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.references()[0]["T1  - "], "Mapped");
+        assert!(format!("{:?}", parsed).starts_with("ParsedFile { references: ["));
+        drop(parsed);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_file_short() {
+        let parser = RisParser::default();
+        for (name, contents) in [
+            ("empty.ris", &b""[..]),
+            ("short.ris", b"\n"),
+            ("bom.ris", b"\xef\xbb"),
+        ] {
+            let path = temp_file(name, contents);
+            let parsed = unsafe { parser.parse_file(&path) }.unwrap();
+            assert!(parsed.is_empty());
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_file_decoded() {
+        let path = temp_file("latin1.ris", b"TY  - JOUR\nT1  - Caf\xe9\nER  - \n");
+        let parser = RisParser::default();
+        assert!(matches!(
+            unsafe { parser.parse_file(&path) },
+            Err(Error::InvalidUtf8(..))
+        ));
+        let parsed = unsafe { parser.parse_file_with_encoding(&path, None, false) }.unwrap();
+        assert_eq!(parsed.references()[0]["T1  - "], "Caf\u{e9}");
+        let parsed =
+            unsafe { parser.parse_file_with_encoding(&path, Some(Encoding::Utf8), true) }.unwrap();
+        assert_eq!(parsed.references()[0]["T1  - "], "Caf\u{fffd}");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_file_errors() {
+        let parser = RisParser::default();
+        let missing = std::env::temp_dir().join("ris-does-not-exist.ris");
+        assert!(matches!(
+            unsafe { parser.parse_file(missing) },
+            Err(Error::Io(..))
+        ));
+
+        let path = temp_file("truncated.ris", b"TY  - JOUR\n");
+        assert!(matches!(
+            unsafe { parser.parse_file(&path) },
+            Err(Error::EOF(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ValueError;
+
+/// Characters that separate the first and last page of a range.
+const SEPARATORS: [char; 5] = ['-', '\u{2010}', '\u{2011}', '\u{2013}', '\u{2014}'];
+
+/// The pages of a reference, from the `SP` and `EP` fields.
+///
+/// `SP` may hold a single page, an article number such as `e0815`, or a whole
+/// range such as `123-145`. Abbreviated last pages, such as the `45` in `123–45`,
+/// are completed from the first page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PageRange {
+    pub start: String,
+    pub end: Option<String>,
+}
+
+impl PageRange {
+    /// Parse the pages from the contents of `SP` and `EP`.
+    ///
+    /// A range in `SP` takes precedence over `EP`. Fails if `SP` is empty.
+    pub fn parse(start: &str, end: Option<&str>) -> Result<Self, ValueError> {
+        let start = start.trim();
+        if start.is_empty() {
+            return Err(ValueError::new("start page should not be empty"));
+        }
+        let (start, end) = match split_range(start) {
+            Some((start, end)) => (start, Some(end)),
+            None => (start, end.map(str::trim).filter(|end| !end.is_empty())),
+        };
+        Ok(Self {
+            start: start.to_owned(),
+            end: end.map(|end| expand(start, end)),
+        })
+    }
+
+    /// Whether the start is an article number, such as `e0815`, rather than a
+    /// page number.
+    pub fn is_article_number(&self) -> bool {
+        self.end.is_none() && !self.start.bytes().all(|c| c.is_ascii_digit())
+    }
+
+    /// The number of pages, if both pages are numbers.
+    pub fn count(&self) -> Option<u32> {
+        let start: u32 = self.start.parse().ok()?;
+        let end: u32 = match &self.end {
+            Some(end) => end.parse().ok()?,
+            None => start,
+        };
+        end.checked_sub(start).map(|pages| pages + 1)
+    }
+
+    /// The canonical contents of `SP` and `EP`. A single page has no `EP`.
+    pub fn to_fields(&self) -> (&str, Option<&str>) {
+        let end = self.end.as_deref().filter(|end| *end != self.start);
+        (&self.start, end)
+    }
+}
+
+impl FromStr for PageRange {
+    type Err = ValueError;
+
+    /// Parse pages as they are written in `SP`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, None)
+    }
+}
+
+impl fmt::Display for PageRange {
+    /// Format the pages as `start-end`, or as `start` for a single page.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_fields() {
+            (start, Some(end)) => write!(f, "{}-{}", start, end),
+            (start, None) => f.write_str(start),
+        }
+    }
+}
+
+/// Split a range into its first and last page.
+///
+/// Only splits if both pages are left, and the first page has a digit or is a roman
+/// numeral, so that article numbers such as `e-0815` are kept whole.
+fn split_range(s: &str) -> Option<(&str, &str)> {
+    let (start, end) = s.split_once(SEPARATORS)?;
+    let start = start.trim();
+    let end = end.trim_start_matches(SEPARATORS).trim();
+    let is_page = start.bytes().any(|c| c.is_ascii_digit())
+        || start.bytes().all(|c| b"ivxlcdmIVXLCDM".contains(&c));
+    (!start.is_empty() && !end.is_empty() && is_page).then_some((start, end))
+}
+
+/// Complete an abbreviated last page from the first page, such as `45` after
+/// `123` to `145`.
+///
+/// The first page may have a prefix, such as `S` in `S123`. The last page is kept as
+/// is if it is not shorter than the first, or if completing it would put it before
+/// the first page.
+fn expand(start: &str, end: &str) -> String {
+    let prefix_len = start.len()
+        - start
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .len();
+    let (prefix, start_number) = start.split_at(prefix_len);
+    if !start_number.bytes().all(|c| c.is_ascii_digit())
+        || !end.bytes().all(|c| c.is_ascii_digit())
+        || end.len() >= start_number.len()
+    {
+        return end.to_owned();
+    }
+    let number = format!("{}{}", &start_number[..start_number.len() - end.len()], end);
+    if number.as_str() < start_number {
+        return end.to_owned();
+    }
+    format!("{}{}", prefix, number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(start: &str, end: Option<&str>) -> PageRange {
+        PageRange {
+            start: start.to_owned(),
+            end: end.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            (("123", None), pages("123", None)),
+            (("123", Some("145")), pages("123", Some("145"))),
+            (("123", Some("45")), pages("123", Some("145"))),
+            (("123-145", None), pages("123", Some("145"))),
+            (("123\u{2013}45", Some("145")), pages("123", Some("145"))),
+            (("123 -- 9", None), pages("123", Some("129"))),
+            (("129-5", None), pages("129", Some("5"))),
+            (("S12-5", None), pages("S12", Some("S15"))),
+            (("xii-xv", None), pages("xii", Some("xv"))),
+            (("e0815", None), pages("e0815", None)),
+            (("e-0815", None), pages("e-0815", None)),
+            ((" 7 ", Some(" ")), pages("7", None)),
+        ];
+        for ((start, end), expected) in cases {
+            assert_eq!(PageRange::parse(start, end).unwrap(), expected, "{}", start);
+        }
+        assert!(PageRange::parse("", Some("12")).is_err());
+    }
+
+    #[test]
+    fn test_article_number() {
+        assert!("e0815".parse::<PageRange>().unwrap().is_article_number());
+        assert!(!"815".parse::<PageRange>().unwrap().is_article_number());
+        assert!(!"e1-e5".parse::<PageRange>().unwrap().is_article_number());
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!("123-45".parse::<PageRange>().unwrap().count(), Some(23));
+        assert_eq!("7".parse::<PageRange>().unwrap().count(), Some(1));
+        assert_eq!("e0815".parse::<PageRange>().unwrap().count(), None);
+    }
+
+    #[test]
+    fn test_fields() {
+        let range: PageRange = "123\u{2013}45".parse().unwrap();
+        assert_eq!(range.to_fields(), ("123", Some("145")));
+        assert_eq!(range.to_string(), "123-145");
+        let single = PageRange::parse("12", Some("12")).unwrap();
+        assert_eq!(single.to_fields(), ("12", None));
+        assert_eq!(single.to_string(), "12");
+    }
+}
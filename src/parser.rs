@@ -1,84 +1,599 @@
+use crate::builder::RisParserBuilder;
 use crate::content_iter::ContentIterator;
+use crate::cst::SyntaxTree;
+use crate::encoding::{Encoding, LineDecoder};
+use crate::error::{Location, Warning};
 use crate::hashmap_handler::HashMapHandler;
+use crate::list_handler::{ListHandler, ListOrItem};
+use crate::mapped_file::ParsedFile;
+use crate::preamble::Preamble;
+use crate::reader::ReferenceReader;
+use crate::recognizer::TagPattern;
+use crate::record_handler::{Record, RecordHandler};
+use crate::ref_iter::LineSplitter;
+use crate::reference_handler::{Reference, ReferenceHandler};
+use crate::reference_type::ReferenceType;
+use crate::tag_handler::TagHandler;
+use crate::utils::parse_utf8;
+use crate::Error;
 use crate::Handler;
 use crate::PResult;
 use crate::ReferenceIterator;
-use crate::utils::parse_utf8;
+use crate::Tag;
+use crate::TagSet;
 use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+/// A parsed reference with the warnings raised and the extra fields set aside while
+/// parsing it.
+type Parsed<T> = PResult<(T, Vec<Warning>, Vec<ExtraField>)>;
+
+/// How the parser deals with references that fail to parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Fail the whole parse on the first invalid reference.
+    #[default]
+    Strict,
+    /// Leave out invalid references.
+    SkipInvalid,
+    /// Leave out invalid references and report their errors.
+    Collect,
+}
+
+/// How the parser deals with fields whose tag matches the tag pattern, but is not
+/// one of the allowed tags.
+///
+/// Such lines always start a new field, they are never taken as a continuation of
+/// the previous field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownTagPolicy {
+    /// Keep the field in the reference like any other field.
+    #[default]
+    Keep,
+    /// Leave the field out of the reference and report it as an `ExtraField`.
+    Extra,
+    /// Leave the field out of the reference and raise a `Warning::UnknownTag`.
+    Warn,
+    /// Fail the reference with `Error::UnknownTag`.
+    Reject,
+}
+
+/// How references without end tag are ended.
+///
+/// Without recovery a missing end tag makes the next reference part of the
+/// reference, and fails the last reference with `Error::EOF`. Every entry point
+/// recovers references in the same way. References that are ended by recovery are
+/// flagged in `ReferenceInfo::recovered`, `ReferenceReader::recovered` and
+/// `RisPushParser::recovered`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoundaryRecovery {
+    /// Only end references at their end tag.
+    #[default]
+    Off,
+    /// Also end a reference at the next start tag, and at the end of the input.
+    StartTag,
+    /// Like `StartTag`, and also end a reference after the given number of
+    /// consecutive blank lines.
+    ///
+    /// A blank line in a field, such as between the paragraphs of an abstract,
+    /// ends the reference as well, so a single blank line is only a safe boundary
+    /// for inputs without them.
+    BlankLines(NonZeroUsize),
+}
+
+/// How the type of a reference, the content of its start tag, is validated.
+///
+/// A type is valid if it is one of the types of the RIS specification, see
+/// `ReferenceType`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TypeValidation {
+    /// Accept any type.
+    #[default]
+    Accept,
+    /// Accept any type, and raise a `Warning::UnknownType` for invalid ones.
+    Warn,
+    /// Fail references with an invalid type with `Error::ParserError`.
+    Reject,
+}
+
+/// A field with a tag that is not allowed, set aside by `UnknownTagPolicy::Extra`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraField {
+    pub tag: String,
+    pub content: String,
+    /// Location of the tag, including the index of its reference.
+    pub location: Location,
+}
+
+impl ExtraField {
+    pub(crate) fn relative_to(mut self, base: &Location) -> Self {
+        self.location = self.location.relative_to(base);
+        self
+    }
+}
+
+/// What is known about a reference in the input besides its fields, see
+/// `ParseReport::info`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReferenceInfo {
+    /// The text before the reference that is not part of the header, such as a
+    /// line that numbers the reference.
+    pub preamble: Preamble,
+    /// Whether the end of the reference was inferred because it has no end tag,
+    /// see `BoundaryRecovery`.
+    pub recovered: bool,
+    /// Whether the reference was parsed, invalid references are left out of
+    /// `ParseReport::references`.
+    pub valid: bool,
+}
+
+/// The outcome of parsing an input with `RisParser::parse_report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseReport<T> {
+    /// The valid references, in input order.
+    pub references: Vec<T>,
+    /// The errors of the invalid references, in input order.
+    ///
+    /// Only filled in when parsing with `ParseMode::Collect`.
+    pub errors: Vec<Error>,
+    /// The warnings for the valid references, in input order.
+    pub warnings: Vec<Warning>,
+    /// The fields with unknown tags of the valid references, in input order.
+    ///
+    /// Only filled in with `UnknownTagPolicy::Extra`.
+    pub extras: Vec<ExtraField>,
+    /// The text before the first reference, up to its last blank line, such as the
+    /// header of an export with the name of the database.
+    pub header: Preamble,
+    /// For every reference in the input, valid or not, its preamble and how it was
+    /// parsed, in input order.
+    pub info: Vec<ReferenceInfo>,
+    /// The text after the last reference.
+    pub trailer: Preamble,
+}
 
+/// Parser for RIS input, and for RIS-like formats with other tags.
+///
+/// Tags may differ in width, see `TagSet`. The parser owns its configuration, so it
+/// can be stored and shared between threads. It is configured with
+/// `RisParserBuilder`, or with the `with_*` methods for the default preset.
 #[derive(Debug, Clone)]
-pub struct RisParser<'a, const N: usize> {
-    start_tag: &'a [u8; N],
-    end_tag: &'a [u8; N],
-    allowed_tags: HashSet<&'a [u8; N]>,
+pub struct RisParser {
+    pub(crate) start_tag: Box<[u8]>,
+    pub(crate) end_tag: Box<[u8]>,
+    pub(crate) allowed_tags: TagSet,
+    pub(crate) list_tags: TagSet,
+    pub(crate) tag_pattern: TagPattern,
+    pub(crate) unknown_tags: UnknownTagPolicy,
+    pub(crate) type_validation: TypeValidation,
+    pub(crate) boundary_recovery: BoundaryRecovery,
+    pub(crate) parallel: bool,
+    pub(crate) mode: ParseMode,
+    pub(crate) lenient_tags: bool,
 }
 
-impl<'a, const N: usize> RisParser<'a, N> {
-    pub fn new(handler: HashMapHandler<'a, '_,  &str, N>) -> Self {
-        return Self {
-            start_tag: handler.start_tag(),
-            end_tag: handler.end_tag(),
-            allowed_tags: handler.allowed_tags().clone(),
-        };
+impl RisParser {
+    pub fn new(handler: HashMapHandler<'_, '_, &str>) -> Self {
+        RisParserBuilder::new(handler.start_tag(), handler.end_tag())
+            .with_allowed_tags(handler.allowed_tags().iter())
+            .build()
+    }
+
+    /// Start configuring a parser with the given start and end tags.
+    pub fn builder(start_tag: impl AsRef<[u8]>, end_tag: impl AsRef<[u8]>) -> RisParserBuilder {
+        RisParserBuilder::new(start_tag, end_tag)
+    }
+
+    /// Set the tags that may occur multiple times in a reference.
+    ///
+    /// These tags are collected into a list by `parse_lists`, all other tags keep
+    /// only their last value. List tags are allowed tags as well.
+    pub fn with_list_tags<T: AsRef<[u8]>>(
+        mut self,
+        list_tags: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.list_tags = list_tags.into_iter().collect();
+        self.allowed_tags.extend(self.list_tags.iter());
+        self
+    }
+
+    /// Set how invalid references are handled.
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set how the type of a reference is validated, which is not done by default.
+    ///
+    /// The type is the content of the start tag, so this is meant for RIS input.
+    pub fn with_type_validation(mut self, type_validation: TypeValidation) -> Self {
+        self.type_validation = type_validation;
+        self
+    }
+
+    /// Set how references without end tag are ended, which they are not by
+    /// default.
+    pub fn with_boundary_recovery(mut self, boundary_recovery: BoundaryRecovery) -> Self {
+        self.boundary_recovery = boundary_recovery;
+        self
+    }
+
+    /// Accept tags in other case or with other spacing than the configured tags,
+    /// such as `ty  - ` or `TY\t- `. Each such tag results in a warning.
+    ///
+    /// A tag in other case with other spacing, such as `is - `, is not accepted, as
+    /// it is more likely a line of wrapped text.
+    ///
+    /// Only tags of the form `XX  - ` have variants. A tag without its trailing space
+    /// at the end of a line, such as `ER  -`, is always accepted.
+    pub fn with_lenient_tags(mut self, lenient_tags: bool) -> Self {
+        self.lenient_tags = lenient_tags;
+        self
     }
 }
 
-impl<'a, const N: usize> RisParser<'a, N> {
+impl RisParser {
+    /// Parse the input into a map per reference.
+    ///
+    /// The references borrow from the input, which must therefore be UTF-8. Input in
+    /// other encodings is transcoded with `Encoding::decode` first. For the same
+    /// reason, content that spans multiple lines keeps the line endings between its
+    /// lines as they are in the input, such as `\r\n`. Only the line ending before
+    /// the next tag is left out. The other entry points do the same.
     pub fn parse<'b>(&self, input: &'b [u8]) -> PResult<Vec<HashMap<&'b str, &'b str>>> {
-        ReferenceIterator::new(self.start_tag, self.end_tag, &input)
+        Ok(self.parse_report(input)?.references)
+    }
+
+    /// Parse the input, keeping every value of the list tags in source order.
+    pub fn parse_lists<'b>(
+        &self,
+        input: &'b [u8],
+    ) -> PResult<Vec<HashMap<&'b str, ListOrItem<&'b str>>>> {
+        Ok(self.parse_lists_report(input)?.references)
+    }
+
+    /// Parse the input into maps keyed by `Tag`. Like `parse`, only the last value of
+    /// a repeated tag is kept.
+    pub fn parse_tagged<'b>(&self, input: &'b [u8]) -> PResult<Vec<HashMap<Tag, &'b str>>> {
+        Ok(self
+            .parse_records(input)?
             .into_iter()
-            .par_bridge()
-            .map(|ref_string| self.parse_reference(ref_string?))
-            .collect()
+            .map(|record| record.into_iter().collect())
+            .collect())
+    }
+
+    /// Parse the input into a `Record` per reference, which keeps the fields in
+    /// input order and every value of repeated tags.
+    pub fn parse_records<'b>(&self, input: &'b [u8]) -> PResult<Vec<Record<'b>>> {
+        Ok(self.parse_records_report(input)?.references)
+    }
+
+    /// Parse the input into a `Reference` per reference, which has typed accessors
+    /// for the common bibliographic fields.
+    pub fn parse_typed<'b>(&self, input: &'b [u8]) -> PResult<Vec<Reference<'b>>> {
+        Ok(self.parse_typed_report(input)?.references)
+    }
+
+    /// Parse the input into a lossless syntax tree, which keeps every byte of the
+    /// input so that it can be edited and written back, see `SyntaxTree`.
+    ///
+    /// The input is not decoded and fields are not checked against the unknown tag
+    /// policy or the type validation.
+    pub fn parse_cst<'b>(&self, input: &'b [u8]) -> PResult<SyntaxTree<'b>> {
+        SyntaxTree::parse(self, input)
+    }
+
+    /// Memory-map the UTF-8 file at `path` and parse it like `parse`.
+    ///
+    /// The references borrow from the returned guard, which owns the mapping, so the
+    /// file is not copied into memory. Use `parse` on the contents of the file read
+    /// with `std::fs::read` if the file may change.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or modified, by this or any other process,
+    /// while the guard is alive. The references point into the mapped memory, so a
+    /// change would make them invalid UTF-8 or make reading them fail with a bus
+    /// error.
+    pub unsafe fn parse_file(&self, path: impl AsRef<Path>) -> PResult<ParsedFile> {
+        self.parse_file_with_encoding(path, Some(Encoding::Utf8), false)
+    }
+
+    /// Same as `parse_file`, but for a file in `encoding`, which is transcoded with
+    /// `Encoding::decode` unless it is UTF-8 already. `None` detects the encoding
+    /// with `Encoding::detect`.
+    ///
+    /// # Safety
+    ///
+    /// See `parse_file`. A transcoded file is copied and may change after this
+    /// returns.
+    pub unsafe fn parse_file_with_encoding(
+        &self,
+        path: impl AsRef<Path>,
+        encoding: Option<Encoding>,
+        lossy: bool,
+    ) -> PResult<ParsedFile> {
+        ParsedFile::open(self, path, encoding, lossy)
+    }
+
+    /// Read and parse references one by one from `reader`.
+    ///
+    /// Unlike `parse`, the input does not have to be in memory as a whole, only the
+    /// reference that is being parsed is. References are parsed sequentially. In
+    /// strict mode the iterator ends after the first error, in `SkipInvalid` mode
+    /// invalid references are left out and in `Collect` mode their errors are
+    /// returned in between the references. Read errors and truncated references
+    /// always end the iterator.
+    pub fn parse_reader<R: BufRead>(&self, reader: R) -> ReferenceReader<'_, R> {
+        ReferenceReader::new(self, reader)
+    }
+
+    /// Parse the input, reporting the errors of invalid references according to
+    /// the parse mode.
+    pub fn parse_report<'b>(
+        &self,
+        input: &'b [u8],
+    ) -> PResult<ParseReport<HashMap<&'b str, &'b str>>> {
+        self.parse_references(input, |reference| self.parse_reference(reference))
+    }
+
+    /// Same as `parse_report`, but with the output of `parse_lists`.
+    pub fn parse_lists_report<'b>(
+        &self,
+        input: &'b [u8],
+    ) -> PResult<ParseReport<HashMap<&'b str, ListOrItem<&'b str>>>> {
+        self.parse_references(input, |reference| self.parse_reference_lists(reference))
+    }
+
+    /// Same as `parse_report`, but with the output of `parse_records`.
+    pub fn parse_records_report<'b>(&self, input: &'b [u8]) -> PResult<ParseReport<Record<'b>>> {
+        self.parse_references(input, |reference| {
+            self.parse_with_handler(reference, self.record_handler())
+        })
+    }
+
+    /// Same as `parse_report`, but with the output of `parse_typed`.
+    pub fn parse_typed_report<'b>(&self, input: &'b [u8]) -> PResult<ParseReport<Reference<'b>>> {
+        self.parse_references(input, |reference| {
+            self.parse_with_handler(reference, ReferenceHandler::new(self.record_handler()))
+        })
+    }
+
+    fn record_handler<'b>(&self) -> RecordHandler<'_, 'b> {
+        RecordHandler::new(TagHandler::new(
+            &self.start_tag,
+            &self.end_tag,
+            &self.allowed_tags,
+        ))
     }
 
-    fn parse_reference<'b>(&'a self, input: &'b [u8]) -> PResult<HashMap<&'b str, &'b str>> {
-        let mut handler: HashMapHandler<'a, 'b, &'b str, N> = HashMapHandler::new(self.start_tag, self.end_tag, &self.allowed_tags);
+    /// Split the input into references and parse them in parallel.
+    ///
+    /// Splitting is sequential and only collects slices of the input, the
+    /// per-reference parsing is done by rayon on an indexed iterator. The output
+    /// is therefore always in input order, and in strict mode the error of the
+    /// first invalid reference in the input is returned.
+    fn parse_references<'b, T, F>(
+        &self,
+        input: &'b [u8],
+        parse_reference: F,
+    ) -> PResult<ParseReport<T>>
+    where
+        T: Send,
+        F: Fn(&'b [u8]) -> Parsed<T> + Send + Sync,
+    {
+        let mut references: Vec<PResult<(Location, &'b [u8])>> = Vec::new();
+        let mut skipped: Vec<&'b [u8]> = Vec::new();
+        let mut recovered: Vec<bool> = Vec::new();
+        let mut ref_iter = ReferenceIterator::new(&self.start_tag, &self.end_tag, input)
+            .with_lenient_tags(self.lenient_tags)
+            .with_boundary_recovery(self.boundary_recovery);
+        while let Some(reference) = ref_iter.next() {
+            references.push(reference.map(|reference| (ref_iter.location().clone(), reference)));
+            skipped.push(ref_iter.skipped());
+            recovered.push(ref_iter.recovered());
+        }
+        let trailer = Preamble::from(ref_iter.skipped());
+        let (header, first_preamble) = match skipped.first() {
+            Some(text) => Preamble::split_header(text),
+            None => Default::default(),
+        };
+        let parse = |reference: PResult<(Location, &'b [u8])>| {
+            let (location, reference) = reference?;
+            match parse_reference(reference) {
+                Ok((reference, warnings, extras)) => {
+                    let (warnings, extras) = relative_to(warnings, extras, &location);
+                    Ok((reference, warnings, extras))
+                }
+                Err(e) => Err(e.relative_to(&location)),
+            }
+        };
+        let results: Vec<Parsed<T>> = if self.parallel {
+            references.into_par_iter().map(parse).collect()
+        } else {
+            references.into_iter().map(parse).collect()
+        };
+
+        let mut report = ParseReport {
+            references: Vec::with_capacity(results.len()),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            extras: Vec::new(),
+            header,
+            info: Vec::with_capacity(results.len()),
+            trailer,
+        };
+        let mut first_preamble = Some(first_preamble);
+        for ((result, skipped), recovered) in results.into_iter().zip(skipped).zip(recovered) {
+            report.info.push(ReferenceInfo {
+                preamble: first_preamble.take().unwrap_or_else(|| skipped.into()),
+                recovered,
+                valid: result.is_ok(),
+            });
+            match (result, self.mode) {
+                (Ok((reference, warnings, extras)), _) => {
+                    report.references.push(reference);
+                    report.warnings.extend(warnings);
+                    report.extras.extend(extras);
+                }
+                (Err(e), ParseMode::Strict) => return Err(e),
+                (Err(_), ParseMode::SkipInvalid) => {}
+                (Err(e), ParseMode::Collect) => report.errors.push(e),
+            }
+        }
+        Ok(report)
+    }
 
-        for res in ContentIterator::new(&self.allowed_tags, input) {
+    pub(crate) fn parse_reference<'b>(&self, input: &'b [u8]) -> Parsed<HashMap<&'b str, &'b str>> {
+        let handler = HashMapHandler::new(&self.start_tag, &self.end_tag, &self.allowed_tags);
+        self.parse_with_handler(input, handler)
+    }
+
+    /// Parse a single reference that starts at `location` in the input, into owned
+    /// strings. The reference is transcoded with `decoder` first.
+    pub(crate) fn parse_owned_reference(
+        &self,
+        input: &[u8],
+        location: &Location,
+        decoder: &LineDecoder,
+    ) -> Parsed<HashMap<String, String>> {
+        let input = decoder.decode(input).map_err(|e| e.at(location.clone()))?;
+        let (reference, warnings, extras) = self
+            .parse_reference(&input)
+            .map_err(|e| e.relative_to(location))?;
+        let (warnings, extras) = relative_to(warnings, extras, location);
+        Ok((
+            reference
+                .into_iter()
+                .map(|(tag, content)| (tag.to_owned(), content.to_owned()))
+                .collect(),
+            warnings,
+            extras,
+        ))
+    }
+
+    pub(crate) fn line_splitter(&self) -> LineSplitter<'_> {
+        LineSplitter::new(&self.start_tag, &self.end_tag, self.lenient_tags)
+            .with_boundary_recovery(self.boundary_recovery)
+    }
+
+    fn parse_reference_lists<'b>(
+        &self,
+        input: &'b [u8],
+    ) -> Parsed<HashMap<&'b str, ListOrItem<&'b str>>> {
+        let handler = ListHandler::new(
+            HashMapHandler::new(&self.start_tag, &self.end_tag, &self.allowed_tags),
+            &self.list_tags,
+        );
+        self.parse_with_handler(input, handler)
+    }
+
+    fn parse_with_handler<'s, 'b, T, H>(&'s self, input: &'b [u8], mut handler: H) -> Parsed<T>
+    where
+        H: Handler<'s, 'b, &'b str, T>,
+    {
+        let mut warnings = Vec::new();
+        let mut extras = Vec::new();
+        let mut fields = ContentIterator::new(&self.allowed_tags, input)
+            .with_tag_pattern(&self.tag_pattern)
+            .with_lenient_tags(self.lenient_tags);
+        while let Some(res) = fields.next() {
             let (tag, content) = res?;
-            handler.handle(tag, parse_utf8(content)?)?;
+            let field_location = || {
+                let mut location = fields.field_location();
+                location.tag = Some(String::from_utf8_lossy(tag).into_owned());
+                location
+            };
+            if !fields.field_variant().is_canonical() {
+                warnings.push(Warning::TagVariant(
+                    fields.field_variant(),
+                    field_location(),
+                ));
+            }
+            let content = parse_utf8(content).map_err(|e| e.at(field_location()))?;
+            if tag == &*self.start_tag && self.type_validation != TypeValidation::Accept {
+                let reference_type = ReferenceType::from_code(content.trim());
+                if !reference_type.is_known() {
+                    match self.type_validation {
+                        TypeValidation::Warn => {
+                            warnings.push(Warning::UnknownType(reference_type, field_location()))
+                        }
+                        _ => {
+                            return Err(Error::ParserError(
+                                format!("unknown reference type {:?}", content),
+                                field_location(),
+                            ))
+                        }
+                    }
+                }
+            }
+            let handled = if fields.field_known() {
+                handler.handle(tag, content)
+            } else {
+                match self.unknown_tags {
+                    UnknownTagPolicy::Keep => handler.handle_unknown(tag, content),
+                    UnknownTagPolicy::Extra => {
+                        extras.push(ExtraField {
+                            tag: String::from_utf8_lossy(tag).into_owned(),
+                            content: content.to_owned(),
+                            location: field_location(),
+                        });
+                        Ok(())
+                    }
+                    UnknownTagPolicy::Warn => {
+                        warnings.push(Warning::UnknownTag(field_location()));
+                        Ok(())
+                    }
+                    UnknownTagPolicy::Reject => Err(Error::UnknownTag(
+                        "tag should be in allowed tags".into(),
+                        Location::default(),
+                    )),
+                }
+            };
+            handled.map_err(|e| e.at(field_location()))?;
         }
-        Ok(handler.finish())
+        Ok((handler.finish(), warnings, extras))
     }
 }
 
-impl Default for RisParser<'_, 6> {
+/// Translate the locations of the warnings and extra fields of a reference, see
+/// `Location::relative_to`.
+fn relative_to(
+    warnings: Vec<Warning>,
+    extras: Vec<ExtraField>,
+    base: &Location,
+) -> (Vec<Warning>, Vec<ExtraField>) {
+    (
+        warnings
+            .into_iter()
+            .map(|warning| warning.relative_to(base))
+            .collect(),
+        extras
+            .into_iter()
+            .map(|extra| extra.relative_to(base))
+            .collect(),
+    )
+}
+
+impl Default for RisParser {
     fn default() -> Self {
-        Self {
-            start_tag: b"TY  - ",
-            end_tag: b"ER  - ",
-            allowed_tags: HashSet::from([
-                b"TY  - ", b"A1  - ", b"A2  - ", b"A3  - ", b"A4  - ", b"AB  - ", b"AD  - ",
-                b"AN  - ", b"AU  - ", b"C1  - ", b"C2  - ", b"C3  - ", b"C4  - ", b"C5  - ",
-                b"C6  - ", b"C7  - ", b"C8  - ", b"CA  - ", b"CN  - ", b"CY  - ", b"DA  - ",
-                b"DB  - ", b"DO  - ", b"DP  - ", b"ET  - ", b"EP  - ", b"ID  - ", b"IS  - ",
-                b"J2  - ", b"JA  - ", b"JF  - ", b"JO  - ", b"KW  - ", b"L1  - ", b"L2  - ",
-                b"L4  - ", b"LA  - ", b"LB  - ", b"M1  - ", b"M3  - ", b"N1  - ", b"N2  - ",
-                b"NV  - ", b"OP  - ", b"PB  - ", b"PY  - ", b"RI  - ", b"RN  - ", b"RP  - ",
-                b"SE  - ", b"SN  - ", b"SP  - ", b"ST  - ", b"T1  - ", b"T2  - ", b"T3  - ",
-                b"TA  - ", b"TI  - ", b"TT  - ", b"UR  - ", b"VL  - ", b"Y1  - ", b"Y2  - ",
-                b"UK  - ", b"ER  - ",
-            ]),
-        }
+        RisParserBuilder::default().build()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Identifier;
 
     #[test]
     fn test_parse_reference() {
-        let start_tag = b"TY  - ";
-        let end_tag = b"ER  - ";
-        let allowed_tags = HashSet::from([b"AA  - ", b"AB  - ", b"TY  - ", b"ER  - "]);
-        let parser = RisParser {
-            start_tag,
-            end_tag,
-            allowed_tags,
-        };
+        let parser = RisParser::builder(*b"TY  - ", *b"ER  - ")
+            .with_allowed_tags([*b"AA  - ", *b"AB  - "])
+            .build();
 
         let input = b"TY  - ref_type
 AA  - val1
@@ -89,7 +604,7 @@ ER  - ";
             ("AA  - ", "val1"),
             ("AB  - ", "val2"),
         ]);
-        assert_eq!(parser.parse_reference(input).unwrap(), output);
+        assert_eq!(parser.parse_reference(input).unwrap().0, output);
     }
 
     #[test]
@@ -175,4 +690,732 @@ ER  -
         assert_eq!(*references[1].get("CY  - ").unwrap(), "Germany");
         assert_eq!(*references[1].get("M1  - ").unwrap(), "1228150341");
     }
+
+    #[test]
+    fn test_parse_lists() {
+        let input = b"TY  - JOUR
+A1  - Marx, Karl
+A1  - Lindgren, Astrid
+KW  - Pippi
+KW  - Nordwind
+KW  - Piraten
+T1  - First title
+T1  - Second title
+ER  - ";
+        let parser = RisParser::default();
+
+        let references = parser.parse_lists(input).unwrap();
+        assert_eq!(references.len(), 1);
+        assert_eq!(
+            references[0],
+            HashMap::from([
+                ("TY  - ", ListOrItem::Item("JOUR")),
+                (
+                    "A1  - ",
+                    ListOrItem::List(vec!["Marx, Karl", "Lindgren, Astrid"])
+                ),
+                (
+                    "KW  - ",
+                    ListOrItem::List(vec!["Pippi", "Nordwind", "Piraten"])
+                ),
+                ("T1  - ", ListOrItem::Item("Second title")),
+            ])
+        );
+    }
+
+    /// Build an input with `n` references whose `ID` is their index.
+    fn numbered_references(n: usize) -> String {
+        (0..n)
+            .map(|i| {
+                format!(
+                    "{}.\nTY  - JOUR\nID  - {}\nA1  - Author {}\nA1  - Other author\nER  - \n\n",
+                    i + 1,
+                    i,
+                    i
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_keeps_input_order() {
+        let input = numbered_references(20_000);
+        let parser = RisParser::default();
+
+        let references = parser.parse(input.as_bytes()).unwrap();
+        assert_eq!(references.len(), 20_000);
+        for (i, reference) in references.iter().enumerate() {
+            assert_eq!(*reference.get("ID  - ").unwrap(), i.to_string());
+        }
+    }
+
+    #[test]
+    fn test_parse_lists_keeps_input_order() {
+        let input = numbered_references(20_000);
+        let parser = RisParser::default();
+
+        let references = parser.parse_lists(input.as_bytes()).unwrap();
+        assert_eq!(references.len(), 20_000);
+        for (i, reference) in references.iter().enumerate() {
+            assert_eq!(
+                reference.get("ID  - ").unwrap(),
+                &ListOrItem::Item(i.to_string().as_str())
+            );
+            assert_eq!(
+                reference.get("A1  - ").unwrap(),
+                &ListOrItem::List(vec![format!("Author {}", i).as_str(), "Other author"])
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_is_deterministic() {
+        let input = numbered_references(5_000);
+        let parser = RisParser::default();
+
+        let first = parser.parse(input.as_bytes()).unwrap();
+        for _ in 0..5 {
+            assert_eq!(parser.parse(input.as_bytes()).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn test_error_location() {
+        let parser = RisParser::default();
+        let input = b"TY  - JOUR
+ER  - 
+
+TY  - JOUR
+T1  - \xff
+ER  - 
+";
+        let err = parser.parse(input).unwrap_err();
+        assert!(matches!(err, Error::InvalidUtf8(..)));
+        assert_eq!(
+            err.location(),
+            &Location {
+                offset: 30,
+                line: 5,
+                column: 1,
+                reference: Some(1),
+                tag: Some("T1  - ".into()),
+            }
+        );
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn test_eof_location() {
+        let parser = RisParser::default();
+        let input = b"TY  - JOUR
+ER  - 
+TY  - JOUR
+T1  - unterminated";
+        let err = parser.parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            Error::EOF(Location {
+                offset: 18,
+                line: 3,
+                column: 1,
+                reference: Some(1),
+                tag: None,
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "End of input reached during parsing at line 3, column 1 (byte 18), reference 1"
+        );
+    }
+
+    #[test]
+    fn test_first_error_is_returned() {
+        let parser = RisParser::default();
+        let mut input = numbered_references(1_000);
+        input.push_str("TY  - JOUR\nT1  - \u{0}\n");
+        let mut bytes = input.into_bytes();
+        // Break the utf-8 in references 10 and 500.
+        for id in [b"ID  - 10\n".as_slice(), b"ID  - 500\n".as_slice()] {
+            let pos = bytes.windows(id.len()).position(|w| w == id).unwrap();
+            bytes[pos + id.len() - 2] = 0xff;
+        }
+        let err = parser.parse(&bytes).unwrap_err();
+        assert_eq!(err.location().reference, Some(10));
+    }
+
+    const BROKEN_INPUT: &[u8] = b"TY  - JOUR
+ID  - 1
+ER  - 
+TY  - JOUR
+ID  - \xff
+ER  - 
+TY  - JOUR
+ID  - 3
+ER  - 
+TY  - JOUR
+ID  - 4
+";
+
+    #[test]
+    fn test_strict_mode() {
+        let parser = RisParser::default().with_mode(ParseMode::Strict);
+        let err = parser.parse_report(BROKEN_INPUT).unwrap_err();
+        assert_eq!(err.location().reference, Some(1));
+    }
+
+    #[test]
+    fn test_skip_invalid_mode() {
+        let parser = RisParser::default().with_mode(ParseMode::SkipInvalid);
+        let report = parser.parse_report(BROKEN_INPUT).unwrap();
+        let ids: Vec<&str> = report.references.iter().map(|r| r["ID  - "]).collect();
+        assert_eq!(ids, ["1", "3"]);
+        assert!(report.errors.is_empty());
+        assert_eq!(parser.parse(BROKEN_INPUT).unwrap(), report.references);
+    }
+
+    #[test]
+    fn test_collect_mode() {
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let report = parser.parse_lists_report(BROKEN_INPUT).unwrap();
+        assert_eq!(report.references.len(), 2);
+        assert_eq!(report.errors.len(), 2);
+        assert!(matches!(report.errors[0], Error::InvalidUtf8(..)));
+        assert_eq!(report.errors[0].location().line, 5);
+        assert_eq!(report.errors[0].location().reference, Some(1));
+        assert!(matches!(report.errors[1], Error::EOF(..)));
+        assert_eq!(report.errors[1].location().line, 10);
+        assert_eq!(report.errors[1].location().reference, Some(3));
+    }
+
+    #[test]
+    fn test_mixed_line_endings() {
+        let input = b"TY  - JOUR\r\nT1  - Windows\r\nKW  - a\r\nKW  - b\r\nER  - \r\n\r\n\
+TY  - JOUR\nT1  - Unix\nER  - \n\n\
+TY  - JOUR\rT1  - Classic Mac\rAB  - first\rsecond\rER  - \r";
+        let parser = RisParser::default();
+
+        let references = parser.parse(input).unwrap();
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0]["T1  - "], "Windows");
+        assert_eq!(references[0]["KW  - "], "b");
+        assert_eq!(references[1]["T1  - "], "Unix");
+        assert_eq!(references[2]["T1  - "], "Classic Mac");
+        assert_eq!(references[2]["AB  - "], "first\rsecond");
+
+        let references = parser.parse_lists(input).unwrap();
+        assert_eq!(references[0]["KW  - "], ListOrItem::List(vec!["a", "b"]));
+    }
+
+    #[test]
+    fn test_multiline_content_line_endings() {
+        let input = b"TY  - JOUR\r\nAB  - first\r\nsecond\nthird\r\nER  - \r\n";
+        let parser = RisParser::default();
+        let references = parser.parse(input).unwrap();
+        assert_eq!(references[0]["AB  - "], "first\r\nsecond\nthird");
+
+        let references: Vec<_> = parser.parse_reader(input.as_slice()).collect();
+        assert_eq!(
+            references[0].as_ref().unwrap()["AB  - "],
+            "first\r\nsecond\nthird"
+        );
+        let mut push_parser = crate::RisPushParser::new(&parser);
+        let references = push_parser.feed(input);
+        assert_eq!(
+            references[0].as_ref().unwrap()["AB  - "],
+            "first\r\nsecond\nthird"
+        );
+    }
+
+    #[test]
+    fn test_crlf_error_location() {
+        let input = b"TY  - JOUR\r\nER  - \r\nTY  - JOUR\r\nT1  - \xff\r\nER  - \r\n";
+        let err = RisParser::default().parse(input).unwrap_err();
+        assert_eq!(err.location().line, 4);
+        assert_eq!(err.location().offset, 32);
+    }
+
+    #[test]
+    fn test_stripped_end_tag() {
+        let input = b"TY  - JOUR\nT1  - First\nER  -\n\nTY  - JOUR\nT1  - Second\nER  -";
+        let parser = RisParser::default();
+
+        let report = parser.parse_report(input).unwrap();
+        assert_eq!(report.references.len(), 2);
+        assert_eq!(report.references[0]["T1  - "], "First");
+        assert_eq!(report.references[1]["T1  - "], "Second");
+        assert_eq!(report.warnings.len(), 2);
+        assert_eq!(report.warnings[1].location().line, 7);
+    }
+
+    #[test]
+    fn test_lenient_tags() {
+        let input = b"ty  - JOUR\nT1\t-\tTitle\nkw  - a\nKW  - b\ner  -\n";
+        let parser = RisParser::default().with_lenient_tags(true);
+
+        let report = parser.parse_lists_report(input).unwrap();
+        assert_eq!(
+            report.references,
+            vec![HashMap::from([
+                ("TY  - ", ListOrItem::Item("JOUR")),
+                ("T1  - ", ListOrItem::Item("Title")),
+                ("KW  - ", ListOrItem::List(vec!["a", "b"])),
+            ])]
+        );
+        let lines: Vec<usize> = report.warnings.iter().map(|w| w.location().line).collect();
+        assert_eq!(lines, [1, 2, 3, 5]);
+        assert_eq!(
+            report.warnings[0],
+            Warning::TagVariant(
+                crate::TagVariant {
+                    lowercase: true,
+                    irregular_spacing: false,
+                    missing_trailing_space: false,
+                },
+                Location {
+                    offset: 0,
+                    line: 1,
+                    column: 1,
+                    reference: Some(0),
+                    tag: Some("TY  - ".into()),
+                }
+            )
+        );
+
+        assert!(RisParser::default().parse(input).unwrap().is_empty());
+
+        let input = b"TY  - JOUR
+IS  - 3
+AB  - The effect
+is - not clear
+ER  - ";
+        assert_eq!(
+            parser.parse(input).unwrap(),
+            [HashMap::from([
+                ("TY  - ", "JOUR"),
+                ("IS  - ", "3"),
+                ("AB  - ", "The effect\nis - not clear")
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let text = "\u{feff}TY  - JOUR\r\nT1  - Caf\u{e9}\r\nER  - \r\n";
+        let utf16: Vec<u8> = text.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        let decoded = Encoding::detect(&utf16).decode(&utf16, false).unwrap();
+        let references = RisParser::default().parse(&decoded).unwrap();
+        assert_eq!(references[0]["T1  - "], "Caf\u{e9}");
+
+        // Input that is not transcoded is parsed as UTF-8.
+        let input = b"TY  - JOUR\nT1  - Caf\xe9\nER  - \n";
+        let parser = RisParser::default();
+        assert!(matches!(parser.parse(input), Err(Error::InvalidUtf8(..))));
+        let decoded = Encoding::Utf8.decode(input, true).unwrap();
+        assert_eq!(parser.parse(&decoded).unwrap()[0]["T1  - "], "Caf\u{fffd}");
+    }
+
+    #[test]
+    fn test_variable_width_tags() {
+        // A MEDLINE-like format with tags of different widths, delimited like RIS.
+        let parser = RisParser::builder("PMID- ", "ER  - ")
+            .with_allowed_tags(["TI  - ", "FAU - ", "AB - "])
+            .with_list_tags(["FAU - "])
+            .with_tag_pattern(TagPattern::medline())
+            .with_unknown_tags(UnknownTagPolicy::Reject)
+            .build();
+        let input = b"PMID- 1
+TI  - A title
+      continued
+FAU - Marx, Karl
+FAU - Lindgren, Astrid
+AB - Abstract
+ER  - 
+PMID- 2
+TI  - Another title
+MH  - Unknown
+ER  - 
+";
+        let report = parser
+            .clone()
+            .with_mode(ParseMode::Collect)
+            .parse_lists_report(input)
+            .unwrap();
+        assert_eq!(
+            report.references,
+            [HashMap::from([
+                ("PMID- ", ListOrItem::Item("1")),
+                ("TI  - ", ListOrItem::Item("A title\n      continued")),
+                (
+                    "FAU - ",
+                    ListOrItem::List(vec!["Marx, Karl", "Lindgren, Astrid"])
+                ),
+                ("AB - ", ListOrItem::Item("Abstract")),
+            ])]
+        );
+        let err = &report.errors[0];
+        assert!(matches!(err, Error::UnknownTag(..)));
+        assert_eq!(err.location().line, 10);
+        assert_eq!(err.location().tag.as_deref(), Some("MH  - "));
+    }
+
+    #[test]
+    fn test_unknown_tag_policies() {
+        let input = b"TY  - JOUR
+T1  - Title
+continued
+LK  - http://example.com
+ZZ  - Custom
+field
+ER  - ";
+        let parse = |policy| {
+            RisParserBuilder::default()
+                .with_unknown_tags(policy)
+                .with_mode(ParseMode::Collect)
+                .build()
+                .parse_report(input)
+                .unwrap()
+        };
+
+        let report = parse(UnknownTagPolicy::Keep);
+        assert_eq!(
+            report.references,
+            [HashMap::from([
+                ("TY  - ", "JOUR"),
+                ("T1  - ", "Title\ncontinued"),
+                ("LK  - ", "http://example.com"),
+                ("ZZ  - ", "Custom\nfield"),
+            ])]
+        );
+        assert!(report.warnings.is_empty() && report.extras.is_empty());
+
+        let expected = HashMap::from([("TY  - ", "JOUR"), ("T1  - ", "Title\ncontinued")]);
+        let report = parse(UnknownTagPolicy::Extra);
+        assert_eq!(report.references, std::slice::from_ref(&expected));
+        assert_eq!(
+            report.extras[1],
+            ExtraField {
+                tag: "ZZ  - ".into(),
+                content: "Custom\nfield".into(),
+                location: Location {
+                    offset: 58,
+                    line: 5,
+                    column: 1,
+                    reference: Some(0),
+                    tag: Some("ZZ  - ".into()),
+                },
+            }
+        );
+
+        let report = parse(UnknownTagPolicy::Warn);
+        assert_eq!(report.references, [expected]);
+        let lines: Vec<usize> = report.warnings.iter().map(|w| w.location().line).collect();
+        assert_eq!(lines, [4, 5]);
+        assert!(matches!(report.warnings[0], Warning::UnknownTag(_)));
+
+        let report = parse(UnknownTagPolicy::Reject);
+        assert!(report.references.is_empty());
+        assert!(matches!(report.errors[0], Error::UnknownTag(..)));
+        assert_eq!(report.errors[0].location().line, 4);
+    }
+
+    #[test]
+    fn test_digit_continuation_line() {
+        let input = b"TY  - JOUR\nAB  - doses of\n10  - 20 mg\nER  - \n";
+        for policy in [UnknownTagPolicy::Keep, UnknownTagPolicy::Reject] {
+            let parser = RisParserBuilder::default()
+                .with_unknown_tags(policy)
+                .build();
+            assert_eq!(
+                parser.parse(input).unwrap(),
+                [HashMap::from([
+                    ("TY  - ", "JOUR"),
+                    ("AB  - ", "doses of\n10  - 20 mg")
+                ])]
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_tagged() {
+        let input = b"TY  - JOUR
+AU  - Marx, Karl
+ZZ  - custom
+AU  - Lindgren, Astrid
+ER  - ";
+        let parser = RisParser::default();
+        assert_eq!(
+            parser.parse_records(input).unwrap()[0].fields,
+            [
+                (Tag::Type, "JOUR"),
+                (Tag::Author, "Marx, Karl"),
+                (Tag::Other("ZZ".into()), "custom"),
+                (Tag::Author, "Lindgren, Astrid"),
+            ]
+        );
+        let references = parser.parse_tagged(input).unwrap();
+        assert_eq!(references[0][&Tag::Author], "Lindgren, Astrid");
+        assert_eq!(references[0].len(), 3);
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let input = b"TY  - JOUR
+AU  - Marx, Karl
+ZZ  - custom
+AU  - Lindgren, Astrid
+ER  - 
+TY  - BOOK
+T1  - \xff
+ER  - ";
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let report = parser.parse_records_report(input).unwrap();
+        assert_eq!(report.references.len(), 1);
+        assert_eq!(report.errors.len(), 1);
+        let record = &report.references[0];
+        assert_eq!(record.first(&Tag::Author), Some("Marx, Karl"));
+        assert_eq!(record.get(&Tag::Other("ZZ".into())), Some("custom"));
+        assert_eq!(record.get_all(&Tag::Author).count(), 2);
+        assert_eq!(record.fields[2], (Tag::Other("ZZ".into()), "custom"));
+    }
+
+    #[test]
+    fn test_parse_typed() {
+        let input = b"TY  - JOUR
+TI  - The title
+AU  - Marx, Karl
+A1  - Lindgren, Astrid
+PY  - 2019///
+JF  - Journal of Things
+VL  - 12
+IS  - 3
+SP  - 100
+EP  - 110
+DO  - 10.1000/xyz
+N2  - The abstract
+KW  - one
+KW  - two
+ZZ  - custom
+ER  - ";
+        let references = RisParser::default().parse_typed(input).unwrap();
+        let reference = &references[0];
+        assert_eq!(reference.title(), Some("The title"));
+        assert_eq!(reference.author_names(), ["Marx, Karl", "Lindgren, Astrid"]);
+        assert_eq!(reference.authors()[0].given.as_deref(), Some("Karl"));
+        assert_eq!(reference.year(), Some(2019));
+        assert_eq!(reference.journal(), Some("Journal of Things"));
+        assert_eq!(reference.volume(), Some("12"));
+        assert_eq!(reference.issue(), Some("3"));
+        assert_eq!(reference.start_page(), Some("100"));
+        assert_eq!(reference.end_page(), Some("110"));
+        assert_eq!(reference.pages().unwrap().count(), Some(11));
+        assert_eq!(reference.doi(), Some("10.1000/xyz"));
+        assert_eq!(
+            reference.identifiers(),
+            [Identifier::Doi("10.1000/xyz".into())]
+        );
+        assert_eq!(reference.abstract_text(), Some("The abstract"));
+        assert_eq!(reference.keywords(), ["one", "two"]);
+        assert_eq!(
+            reference.record().get(&Tag::Other("ZZ".into())),
+            Some("custom")
+        );
+    }
+
+    #[test]
+    fn test_type_validation() {
+        let input = b"TY  - JOUR
+ER  - 
+TY  - WEIRD
+ER  - ";
+        let parser = RisParser::default();
+        assert_eq!(parser.parse(input).unwrap().len(), 2);
+
+        let parser = RisParser::default().with_type_validation(TypeValidation::Warn);
+        let report = parser.parse_report(input).unwrap();
+        assert_eq!(report.references.len(), 2);
+        assert_eq!(
+            report.warnings,
+            [Warning::UnknownType(
+                ReferenceType::Other("WEIRD".into()),
+                Location {
+                    offset: 18,
+                    line: 3,
+                    column: 1,
+                    reference: Some(1),
+                    tag: Some("TY  - ".into()),
+                }
+            )]
+        );
+
+        let parser = RisParser::default()
+            .with_type_validation(TypeValidation::Reject)
+            .with_mode(ParseMode::Collect);
+        let report = parser.parse_report(input).unwrap();
+        assert_eq!(report.references.len(), 1);
+        assert!(matches!(report.errors[0], Error::ParserError(..)));
+        assert_eq!(report.errors[0].location().line, 3);
+    }
+
+    #[test]
+    fn test_preambles() {
+        let input = b"Provider: Ovid Technologies
+Database: Embase <1974 to 2024>
+
+1.
+TY  - JOUR
+ER  - 
+
+2.
+TY  - JOUR
+T1  - \xff
+ER  - 
+3.
+TY  - BOOK
+ER  - 
+end of export
+";
+        let parser = RisParser::default().with_mode(ParseMode::SkipInvalid);
+        let report = parser.parse_records_report(input).unwrap();
+        assert_eq!(report.header.get("Database"), Some("Embase <1974 to 2024>"));
+        assert_eq!(
+            report.header.text,
+            "Provider: Ovid Technologies\nDatabase: Embase <1974 to 2024>\n\n"
+        );
+        let preambles: Vec<&str> = report
+            .info
+            .iter()
+            .map(|info| info.preamble.text.as_str())
+            .collect();
+        assert_eq!(preambles, ["1.\n", "\n2.\n", "3.\n"]);
+        assert!(!report.info[1].valid);
+        assert_eq!(report.trailer.text, "end of export\n");
+
+        let mut writer = crate::RisWriter::new(Vec::new());
+        writer.write_text(&report.header.text).unwrap();
+        let valid = report.info.iter().filter(|info| info.valid);
+        for (info, record) in valid.zip(&report.references) {
+            writer.write_text(&info.preamble.text).unwrap();
+            writer.write_record(record).unwrap();
+        }
+        writer.write_text(&report.trailer.text).unwrap();
+        let output = writer.finish().unwrap();
+        let report_again = parser.parse_records_report(&output).unwrap();
+        assert_eq!(report_again.header, report.header);
+        assert_eq!(report_again.references, report.references);
+        assert_eq!(report_again.trailer, report.trailer);
+    }
+
+    #[test]
+    fn test_boundary_recovery() {
+        let input = b"TY  - JOUR
+T1  - Missing end tag
+TY  - JOUR
+T1  - Second
+ER  - 
+1.
+TY  - JOUR
+AB  - First paragraph
+
+Second paragraph
+ER  - 
+2.
+TY  - BOOK
+T1  - Last
+
+
+3.
+TY  - BOOK
+T1  - At the end
+";
+        let parser = RisParser::default();
+        assert!(matches!(parser.parse(input), Err(Error::EOF(..))));
+
+        let parser = RisParser::default().with_boundary_recovery(BoundaryRecovery::StartTag);
+        let report = parser.parse_report(input).unwrap();
+        let titles: Vec<_> = report.references.iter().map(|r| r.get("T1  - ")).collect();
+        assert_eq!(
+            titles,
+            [
+                Some(&"Missing end tag"),
+                Some(&"Second"),
+                None,
+                Some(&"Last\n\n\n3."),
+                Some(&"At the end")
+            ]
+        );
+        let recovered: Vec<bool> = report.info.iter().map(|info| info.recovered).collect();
+        assert_eq!(recovered, [true, false, false, true, true]);
+        assert_eq!(report.info[2].preamble.text, "1.\n");
+
+        let parser = RisParser::default()
+            .with_boundary_recovery(BoundaryRecovery::BlankLines(NonZeroUsize::new(2).unwrap()));
+        let report = parser.parse_report(input).unwrap();
+        assert_eq!(report.references.len(), 5);
+        assert_eq!(
+            report.references[2]["AB  - "],
+            "First paragraph\n\nSecond paragraph"
+        );
+        assert_eq!(report.references[3]["T1  - "], "Last");
+        assert_eq!(report.info[4].preamble.text, "\n\n3.\n");
+        let recovered: Vec<bool> = report.info.iter().map(|info| info.recovered).collect();
+        assert_eq!(recovered, [true, false, false, true, true]);
+
+        // The streaming parsers and the syntax tree recover references in the same way.
+        for recovery in [
+            BoundaryRecovery::StartTag,
+            BoundaryRecovery::BlankLines(NonZeroUsize::new(2).unwrap()),
+        ] {
+            let parser = RisParser::default().with_boundary_recovery(recovery);
+            let report = parser.parse_report(input).unwrap();
+            let expected: Vec<HashMap<String, String>> = report
+                .references
+                .iter()
+                .map(|r| {
+                    r.iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect()
+                })
+                .collect();
+            let preambles: Vec<&str> = report.info.iter().map(|i| &*i.preamble.text).collect();
+
+            let mut reader = parser.parse_reader(&input[..]);
+            let mut references = Vec::new();
+            let mut reader_recovered = Vec::new();
+            let mut reader_preambles = Vec::new();
+            while let Some(reference) = reader.next() {
+                references.push(reference.unwrap());
+                reader_recovered.push(reader.recovered());
+                reader_preambles.push(reader.preamble().text.clone());
+            }
+            assert_eq!(references, expected);
+            assert_eq!(reader_recovered, recovered);
+            assert_eq!(reader_preambles, preambles);
+            assert_eq!(reader.trailer().text, report.trailer.text);
+
+            let mut push_parser = crate::RisPushParser::new(&parser);
+            let mut references = Vec::new();
+            let mut push_recovered = Vec::new();
+            for line in input.split_inclusive(|c| *c == b'\n') {
+                references.extend(push_parser.feed(line));
+                push_recovered.extend_from_slice(push_parser.recovered());
+            }
+            references.extend(push_parser.finish());
+            push_recovered.extend_from_slice(push_parser.recovered());
+            let references: Vec<_> = references.into_iter().collect::<PResult<_>>().unwrap();
+            assert_eq!(references, expected);
+            assert_eq!(push_recovered, recovered);
+
+            let tree = parser.parse_cst(input).unwrap();
+            assert_eq!(tree.to_bytes(), input);
+            let titles: Vec<_> = tree
+                .records()
+                .map(|r| r.field(b"T1  - ").map(|f| f.content.to_vec()))
+                .collect();
+            let expected: Vec<_> = expected
+                .iter()
+                .map(|r| r.get("T1  - ").map(|t| t.as_bytes().to_vec()))
+                .collect();
+            assert_eq!(titles, expected);
+        }
+    }
 }
@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use crate::encoding::LineDecoder;
+
+/// Text outside of the references, such as the header of an export or the `1.`
+/// line that numbers a reference.
+///
+/// The text is kept as it is in the input, so it can be written back with
+/// `RisWriter::write_text`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preamble {
+    pub text: String,
+}
+
+impl Preamble {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// The `Key: value` lines of the text, such as `Database: Ovid MEDLINE(R)`.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.text.lines().filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key, value.trim()))
+        })
+    }
+
+    /// The value of the first `Key: value` line with the given key, which is
+    /// compared case-insensitively.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .find(|(entry_key, _)| entry_key.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Split the text before the first reference into the header of the input and
+    /// the preamble of the reference, at the last blank line.
+    pub(crate) fn split_header(text: &[u8]) -> (Self, Self) {
+        let mut split = 0;
+        let mut line_start = 0;
+        for (idx, c) in text.iter().enumerate() {
+            if *c == b'\n' || (*c == b'\r' && text.get(idx + 1) != Some(&b'\n')) {
+                if text[line_start..idx].iter().all(u8::is_ascii_whitespace) {
+                    split = idx + 1;
+                }
+                line_start = idx + 1;
+            }
+        }
+        (Self::from(&text[..split]), Self::from(&text[split..]))
+    }
+}
+
+impl From<&[u8]> for Preamble {
+    fn from(text: &[u8]) -> Self {
+        Self::new(String::from_utf8_lossy(text))
+    }
+}
+
+/// Collects the text outside of the references in input that is read line by line,
+/// for `ReferenceReader` and `RisPushParser`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PreambleCollector {
+    /// The text since the end of the last reference.
+    text: Vec<u8>,
+    header: Preamble,
+    started: bool,
+}
+
+impl PreambleCollector {
+    /// Register a line that is not part of a reference.
+    pub(crate) fn push_line(&mut self, line: &[u8]) {
+        self.text.extend_from_slice(line);
+    }
+
+    /// The preamble of the reference that starts at the next line. The text before
+    /// the first reference is split into the header and its preamble.
+    pub(crate) fn take_preamble(&mut self, decoder: &LineDecoder) -> Preamble {
+        let preamble = self.take(decoder);
+        if self.started {
+            return preamble;
+        }
+        self.started = true;
+        let (header, preamble) = Preamble::split_header(preamble.text.as_bytes());
+        self.header = header;
+        preamble
+    }
+
+    /// The text after the last reference, at the end of the input.
+    pub(crate) fn take_trailer(&mut self, decoder: &LineDecoder) -> Preamble {
+        self.take(decoder)
+    }
+
+    pub(crate) fn header(&self) -> &Preamble {
+        &self.header
+    }
+
+    fn take(&mut self, decoder: &LineDecoder) -> Preamble {
+        let text = std::mem::take(&mut self.text);
+        // Only UTF-16 fails to decode, which is rejected before it gets here.
+        let decoded = decoder.decode(&text).unwrap_or(Cow::Borrowed(&text));
+        Preamble::from(&*decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries() {
+        let preamble = Preamble::new(
+            "Provider: Ovid Technologies\r\nDatabase: Embase <1974 to 2024>\nSearch Strategy:\n",
+        );
+        assert_eq!(preamble.get("database"), Some("Embase <1974 to 2024>"));
+        assert_eq!(preamble.get("Search Strategy"), Some(""));
+        assert_eq!(preamble.get("Content"), None);
+        assert_eq!(preamble.entries().count(), 3);
+    }
+
+    #[test]
+    fn test_split_header() {
+        let (header, preamble) = Preamble::split_header(b"Provider: X\r\n\r\n \n1.\n");
+        assert_eq!(header.text, "Provider: X\r\n\r\n \n");
+        assert_eq!(preamble.text, "1.\n");
+
+        let (header, preamble) = Preamble::split_header(b"1.\n");
+        assert!(header.is_empty());
+        assert_eq!(preamble.text, "1.\n");
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use crate::encoding::LineDecoder;
+use crate::error::Warning;
+use crate::preamble::{Preamble, PreambleCollector};
+use crate::ref_iter::{LineKind, LineSplitter};
+use crate::utils::trim_line_endings;
+use crate::{Encoding, ExtraField, Location, PResult, ParseMode, RisParser};
+
+/// Incremental parser for input that arrives in chunks, such as network uploads or
+/// a file that is still being written.
+///
+/// Chunks may be split anywhere, also within tags, line endings or multi-byte
+/// characters. A reference is returned by `feed` as soon as its end tag line is
+/// complete. Invalid references are handled according to the parse mode of the
+/// parser, in strict mode all input after the first error is ignored.
+pub struct RisPushParser<'p> {
+    parser: &'p RisParser,
+    splitter: LineSplitter<'p>,
+    decoder: LineDecoder,
+    /// Input that does not form a complete line yet.
+    pending: Vec<u8>,
+    /// The length of the start of `pending` that is known not to end a line, so
+    /// that it is not searched again for every chunk.
+    scanned: usize,
+    /// The bytes of the reference that is being read.
+    buffer: Vec<u8>,
+    preambles: PreambleCollector,
+    /// The preamble of the reference that is being read.
+    preamble: Preamble,
+    /// The preambles of the results of the last call to `feed` or `finish`.
+    returned: Vec<Preamble>,
+    /// Whether the ends of these results were inferred.
+    recovered: Vec<bool>,
+    trailer: Preamble,
+    warnings: Vec<Warning>,
+    extras: Vec<ExtraField>,
+    /// Whether further input is ignored, after an error in strict mode or after
+    /// `finish`.
+    done: bool,
+}
+
+impl<'p> RisPushParser<'p> {
+    pub fn new(parser: &'p RisParser) -> Self {
+        Self {
+            parser,
+            splitter: parser.line_splitter(),
+            decoder: LineDecoder::new(Some(Encoding::Utf8), false),
+            pending: Vec::new(),
+            scanned: 0,
+            buffer: Vec::new(),
+            preambles: PreambleCollector::default(),
+            preamble: Preamble::default(),
+            returned: Vec::new(),
+            recovered: Vec::new(),
+            trailer: Preamble::default(),
+            warnings: Vec::new(),
+            extras: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Set the encoding of the input, see `ReferenceReader::with_encoding`. UTF-16
+    /// input results in an error and ends parsing in every parse mode.
+    pub fn with_encoding(mut self, encoding: Option<Encoding>) -> Self {
+        self.decoder.encoding = encoding;
+        self
+    }
+
+    /// Replace invalid characters by U+FFFD when transcoding, instead of failing.
+    pub fn with_lossy(mut self, lossy: bool) -> Self {
+        self.decoder.lossy = lossy;
+        self
+    }
+
+    /// The text before the first reference, up to its last blank line, see
+    /// `ParseReport::header`.
+    pub fn header(&self) -> &Preamble {
+        self.preambles.header()
+    }
+
+    /// The text before each of the references and errors that were returned by the
+    /// last call to `feed` or `finish` that is not part of the header, in the same
+    /// order.
+    pub fn preambles(&self) -> &[Preamble] {
+        &self.returned
+    }
+
+    /// Whether the end of each of the references and errors that were returned by
+    /// the last call to `feed` or `finish` was inferred because it has no end tag,
+    /// see `BoundaryRecovery`. In the same order as `preambles`.
+    pub fn recovered(&self) -> &[bool] {
+        &self.recovered
+    }
+
+    /// The text after the last reference, once `finish` has been called.
+    pub fn trailer(&self) -> &Preamble {
+        &self.trailer
+    }
+
+    /// The warnings raised while parsing the references that were returned by the
+    /// last call to `feed` or `finish`.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The fields with unknown tags set aside from the references that were
+    /// returned by the last call to `feed` or `finish`, see
+    /// `UnknownTagPolicy::Extra`.
+    pub fn extras(&self) -> &[ExtraField] {
+        &self.extras
+    }
+
+    /// Add a chunk of input and return the references that were completed by it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<PResult<HashMap<String, String>>> {
+        let mut references = Vec::new();
+        self.clear();
+        if self.done {
+            return references;
+        }
+        self.pending.extend_from_slice(chunk);
+
+        let pending = std::mem::take(&mut self.pending);
+        let mut consumed = 0;
+        let mut scanned = self.scanned;
+        while let Some(len) = complete_line(&pending[consumed..], scanned) {
+            self.push_line(&pending[consumed..(consumed + len)], &mut references);
+            consumed += len;
+            scanned = 0;
+        }
+        self.pending = pending;
+        self.pending.drain(..consumed);
+        // A trailing `\r` may still be followed by a `\n`, so it is searched again.
+        self.scanned = self.pending.len().saturating_sub(1);
+        references
+    }
+
+    /// Signal the end of the input and return the references that were completed
+    /// by it.
+    ///
+    /// If the input ended within a reference, an `Error::EOF` for it is returned,
+    /// unless it is ended by `BoundaryRecovery`. Input that is fed after this is
+    /// ignored.
+    pub fn finish(&mut self) -> Vec<PResult<HashMap<String, String>>> {
+        let mut references = Vec::new();
+        self.clear();
+        if self.done {
+            return references;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        self.scanned = 0;
+        if !pending.is_empty() {
+            self.push_line(&pending, &mut references);
+        }
+        match self.splitter.finish() {
+            Ok(None) => self.trailer = self.preambles.take_trailer(&self.decoder),
+            Ok(Some(location)) => {
+                self.emit_buffer(&location, self.buffer.len(), true, &mut references);
+                self.trailer = self.preambles.take_trailer(&self.decoder);
+            }
+            Err(e) => self.emit(Err(e), false, &mut references),
+        }
+        self.done = true;
+        references
+    }
+
+    fn clear(&mut self) {
+        self.returned.clear();
+        self.recovered.clear();
+        self.warnings.clear();
+        self.extras.clear();
+    }
+
+    fn push_line(&mut self, line: &[u8], references: &mut Vec<PResult<HashMap<String, String>>>) {
+        if self.done {
+            return;
+        }
+        if let Err(e) = self.decoder.push_line(line) {
+            self.done = true;
+            references.push(Err(e.at(self.splitter.location().clone())));
+            self.returned.push(Preamble::default());
+            self.recovered.push(false);
+            return;
+        }
+        let line = self.splitter.strip_bom(line);
+        match self.splitter.push_line(line) {
+            LineKind::Outside => self.preambles.push_line(line),
+            LineKind::Start => {
+                self.preamble = self.preambles.take_preamble(&self.decoder);
+                self.buffer.clear();
+                self.buffer.extend_from_slice(line);
+            }
+            LineKind::Inside => self.buffer.extend_from_slice(line),
+            LineKind::End(location) => {
+                self.buffer.extend_from_slice(line);
+                self.emit_buffer(&location, self.buffer.len(), false, references);
+            }
+            LineKind::Restart(location) => {
+                self.emit_buffer(&location, self.buffer.len(), true, references);
+                self.preamble = self.preambles.take_preamble(&self.decoder);
+                self.buffer.extend_from_slice(line);
+            }
+            LineKind::Recovered(location, blank) => {
+                let end = self.buffer.len() - blank;
+                self.preambles.push_line(&self.buffer[end..]);
+                self.preambles.push_line(line);
+                self.emit_buffer(&location, end, true, references);
+            }
+        }
+    }
+
+    /// Parse the first `end` bytes of the buffer as the reference that starts at
+    /// `location`, and clear the buffer.
+    fn emit_buffer(
+        &mut self,
+        location: &Location,
+        end: usize,
+        recovered: bool,
+        references: &mut Vec<PResult<HashMap<String, String>>>,
+    ) {
+        let result = self
+            .parser
+            .parse_owned_reference(
+                trim_line_endings(&self.buffer[..end]),
+                location,
+                &self.decoder,
+            )
+            .map(|(reference, warnings, extras)| {
+                self.warnings.extend(warnings);
+                self.extras.extend(extras);
+                reference
+            });
+        self.buffer.clear();
+        self.emit(result, recovered, references);
+    }
+
+    fn emit(
+        &mut self,
+        result: PResult<HashMap<String, String>>,
+        recovered: bool,
+        references: &mut Vec<PResult<HashMap<String, String>>>,
+    ) {
+        if self.done {
+            return;
+        }
+        let preamble = std::mem::take(&mut self.preamble);
+        match (result, self.parser.mode) {
+            (Ok(reference), _) => references.push(Ok(reference)),
+            (Err(e), ParseMode::Strict) => {
+                self.done = true;
+                references.push(Err(e));
+            }
+            (Err(_), ParseMode::SkipInvalid) => return,
+            (Err(e), ParseMode::Collect) => references.push(Err(e)),
+        }
+        self.returned.push(preamble);
+        self.recovered.push(recovered);
+    }
+}
+
+/// Length of the first line in `text`, including its line ending, if the line is
+/// complete.
+///
+/// A `\r` at the end of the text does not complete a line yet, as it may be
+/// followed by a `\n` in the next chunk. The search starts at `from`, the text
+/// before it is known not to contain a line ending.
+fn complete_line(text: &[u8], from: usize) -> Option<usize> {
+    let idx = from
+        + text[from..]
+            .iter()
+            .position(|c| *c == b'\n' || *c == b'\r')?;
+    match (text[idx], text.get(idx + 1)) {
+        (b'\n', _) => Some(idx + 1),
+        (_, Some(b'\n')) => Some(idx + 2),
+        (_, Some(_)) => Some(idx + 1),
+        (_, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    const INPUT: &str = "\u{feff}1.\r
+TY  - JOUR\r
+T1  - Caf\u{e9} \u{a9} \u{1f600}\r
+AB  - First line\r
+second line\r
+ER  - \r
+\r
+2.\r
+TY  - BOOK\r
+A1  - Marx, Karl\r
+ER  - ";
+
+    fn owned(input: &[u8]) -> Vec<HashMap<String, String>> {
+        RisParser::default()
+            .parse(input)
+            .unwrap()
+            .into_iter()
+            .map(|r| {
+                r.into_iter()
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_complete_line() {
+        assert_eq!(complete_line(b"ab\ncd", 0), Some(3));
+        assert_eq!(complete_line(b"ab\r\ncd", 0), Some(4));
+        assert_eq!(complete_line(b"ab\rcd", 0), Some(3));
+        assert_eq!(complete_line(b"ab\r", 0), None);
+        assert_eq!(complete_line(b"ab", 0), None);
+        assert_eq!(complete_line(b"ab\ncd\n", 3), Some(6));
+        assert_eq!(complete_line(b"ab\r\n", 2), Some(4));
+    }
+
+    #[test]
+    fn test_all_split_points() {
+        let input = INPUT.as_bytes();
+        let expected = owned(input);
+        let parser = RisParser::default();
+        for split in 0..=input.len() {
+            let mut push_parser = RisPushParser::new(&parser);
+            let mut references = push_parser.feed(&input[..split]);
+            references.extend(push_parser.feed(&input[split..]));
+            references.extend(push_parser.finish());
+            let references: Vec<_> = references.into_iter().collect::<PResult<_>>().unwrap();
+            assert_eq!(references, expected, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_byte_by_byte() {
+        let input = INPUT.as_bytes();
+        let parser = RisParser::default();
+        let mut push_parser = RisPushParser::new(&parser);
+        let mut completed_at = Vec::new();
+        for (idx, byte) in input.iter().enumerate() {
+            for reference in push_parser.feed(&[*byte]) {
+                completed_at.push((idx, reference.unwrap()));
+            }
+        }
+        // The first reference is returned when the line ending of its end tag is
+        // complete, the last one has no line ending and is returned by `finish`.
+        assert_eq!(completed_at.len(), 1);
+        let last = push_parser.finish();
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].as_ref().unwrap(), &owned(input)[1]);
+        let er_line_end = INPUT.find("ER  - \r\n").unwrap() + 8;
+        assert_eq!(completed_at[0].0, er_line_end - 1);
+        assert_eq!(completed_at[0].1, owned(input)[0]);
+    }
+
+    #[test]
+    fn test_finish_truncated() {
+        let parser = RisParser::default();
+        let mut push_parser = RisPushParser::new(&parser);
+        assert_eq!(push_parser.feed(b"TY  - JOUR\nER  - \nTY  - BO").len(), 1);
+        assert!(push_parser.feed(b"OK\nT1  - Unfinished").is_empty());
+        let references = push_parser.finish();
+        assert_eq!(references.len(), 1);
+        let err = references[0].as_ref().unwrap_err();
+        assert!(matches!(err, Error::EOF(_)));
+        assert_eq!(err.location().line, 3);
+        assert_eq!(err.location().reference, Some(1));
+    }
+
+    #[test]
+    fn test_modes() {
+        let input = b"TY  - JOUR\nT1  - \xff\nER  - \nTY  - JOUR\nT1  - valid\nER  - \n";
+        let parser = RisParser::default();
+        let mut push_parser = RisPushParser::new(&parser);
+        let references = push_parser.feed(input);
+        assert_eq!(references.len(), 1);
+        assert!(references[0].is_err());
+        assert!(push_parser.feed(input).is_empty());
+
+        let parser = RisParser::default().with_mode(ParseMode::SkipInvalid);
+        let mut push_parser = RisPushParser::new(&parser);
+        let references = push_parser.feed(input);
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].as_ref().unwrap()["T1  - "], "valid");
+
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let mut push_parser = RisPushParser::new(&parser);
+        let references = push_parser.feed(input);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].as_ref().unwrap_err().location().line, 2);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let input = b"TY  - JOUR\nT1  - Caf\xe9\nER  - \nTY  - JOUR\nT1  - \xc3\xa9\nER  - \n";
+        let parser = RisParser::default();
+        let mut push_parser = RisPushParser::new(&parser).with_encoding(None);
+        let references: Vec<_> = input
+            .iter()
+            .flat_map(|byte| push_parser.feed(&[*byte]))
+            .collect::<PResult<_>>()
+            .unwrap();
+        assert_eq!(references[0]["T1  - "], "Caf\u{e9}");
+        assert_eq!(references[1]["T1  - "], "\u{c3}\u{a9}");
+
+        let input: Vec<u8> = "TY  - JOUR\nER  - \n"
+            .encode_utf16()
+            .flat_map(|c| c.to_be_bytes())
+            .collect();
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let mut push_parser = RisPushParser::new(&parser).with_encoding(None);
+        let references = push_parser.feed(&input);
+        assert_eq!(references.len(), 1);
+        assert!(references[0].is_err());
+        assert!(push_parser.finish().is_empty());
+    }
+
+    #[test]
+    fn test_preambles() {
+        let input = b"Database: Embase
+
+1.
+TY  - JOUR
+ER  - 
+
+2.
+TY  - JOUR
+T1  - \xff
+ER  - 
+end";
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let mut push_parser = RisPushParser::new(&parser);
+        let references = push_parser.feed(input);
+        assert_eq!(references.len(), 2);
+        assert!(references[1].is_err());
+        assert_eq!(push_parser.header().text, "Database: Embase\n\n");
+        assert_eq!(
+            push_parser.preambles(),
+            [Preamble::new("1.\n"), Preamble::new("\n2.\n")]
+        );
+        assert!(push_parser.finish().is_empty());
+        assert!(push_parser.preambles().is_empty());
+        assert_eq!(push_parser.trailer().text, "end");
+        assert!(push_parser.feed(b"TY  - JOUR\nER  - \n").is_empty());
+    }
+}
@@ -1,16 +1,32 @@
-use crate::RisParser;
+use crate::{ListOrItem, RisParser};
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
 use std::collections::HashMap;
 
+impl<T: IntoPy<PyObject>> IntoPy<PyObject> for ListOrItem<T> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Self::List(values) => values.into_py(py),
+            Self::Item(value) => value.into_py(py),
+        }
+    }
+}
+
 #[pyfunction]
 fn parse(contents: &PyBytes) -> PyResult<Vec<HashMap<&str, &str>>> {
     let parser = RisParser::default();
     Ok(parser.parse(contents.as_bytes())?)
 }
 
+#[pyfunction]
+fn parse_lists(contents: &PyBytes) -> PyResult<Vec<HashMap<&str, ListOrItem<&str>>>> {
+    let parser = RisParser::default();
+    Ok(parser.parse_lists(contents.as_bytes())?)
+}
+
 #[pymodule]
 fn ris(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_lists, m)?)?;
     Ok(())
 }
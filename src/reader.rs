@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use crate::encoding::LineDecoder;
+use crate::error::Warning;
+use crate::preamble::{Preamble, PreambleCollector};
+use crate::ref_iter::{LineKind, LineSplitter};
+use crate::utils::trim_line_endings;
+use crate::{Encoding, Error, ExtraField, PResult, ParseMode, RisParser};
+
+/// Iterator over the references read from a `BufRead`, created by `RisParser::parse_reader`.
+///
+/// Only a single reference is held in memory at a time. References are split on
+/// the start and end tag lines like `ReferenceIterator` does, and are then parsed
+/// one by one with the parser.
+pub struct ReferenceReader<'p, R> {
+    parser: &'p RisParser,
+    reader: R,
+    splitter: LineSplitter<'p>,
+    decoder: LineDecoder,
+    /// The bytes of the reference that is being read.
+    buffer: Vec<u8>,
+    preambles: PreambleCollector,
+    preamble: Preamble,
+    recovered: bool,
+    trailer: Preamble,
+    warnings: Vec<Warning>,
+    extras: Vec<ExtraField>,
+    done: bool,
+}
+
+impl<'p, R: BufRead> ReferenceReader<'p, R> {
+    pub(crate) fn new(parser: &'p RisParser, reader: R) -> Self {
+        Self {
+            parser,
+            reader,
+            splitter: parser.line_splitter(),
+            decoder: LineDecoder::new(Some(Encoding::Utf8), false),
+            buffer: Vec::new(),
+            preambles: PreambleCollector::default(),
+            preamble: Preamble::default(),
+            recovered: false,
+            trailer: Preamble::default(),
+            warnings: Vec::new(),
+            extras: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Set the encoding of the input, which is UTF-8 by default. `None` detects it
+    /// once, from the first line that is not plain ASCII.
+    ///
+    /// UTF-16 cannot be split into lines on single bytes and ends the iterator with
+    /// an error, such input is transcoded with `Encoding::decode` first.
+    pub fn with_encoding(mut self, encoding: Option<Encoding>) -> Self {
+        self.decoder.encoding = encoding;
+        self
+    }
+
+    /// Replace invalid characters by U+FFFD when transcoding, instead of failing.
+    pub fn with_lossy(mut self, lossy: bool) -> Self {
+        self.decoder.lossy = lossy;
+        self
+    }
+
+    /// The text before the first reference, up to its last blank line, see
+    /// `ParseReport::header`.
+    pub fn header(&self) -> &Preamble {
+        self.preambles.header()
+    }
+
+    /// The text before the reference, or the error, that was last returned that is
+    /// not part of the header, see `ParseReport::info`.
+    pub fn preamble(&self) -> &Preamble {
+        &self.preamble
+    }
+
+    /// Whether the end of the reference, or the error, that was last returned was
+    /// inferred because it has no end tag, see `BoundaryRecovery`.
+    pub fn recovered(&self) -> bool {
+        self.recovered
+    }
+
+    /// The text after the last reference, once the iterator has ended.
+    pub fn trailer(&self) -> &Preamble {
+        &self.trailer
+    }
+
+    /// The warnings raised while parsing the reference that was last returned.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The fields with unknown tags set aside from the reference that was last
+    /// returned, see `UnknownTagPolicy::Extra`.
+    pub fn extras(&self) -> &[ExtraField] {
+        &self.extras
+    }
+
+    fn next_reference(&mut self) -> PResult<Option<HashMap<String, String>>> {
+        self.warnings.clear();
+        self.extras.clear();
+        self.recovered = false;
+        if !self.buffer.is_empty() {
+            // The start tag line of this reference ended the previous one.
+            self.preamble = self.preambles.take_preamble(&self.decoder);
+        }
+        // The reference ends at `end` in the buffer, the buffer is kept from `next`.
+        let (location, end, next) = loop {
+            let line_start = self.buffer.len();
+            let read = read_line(&mut self.reader, &mut self.buffer)
+                .map_err(|e| Error::from(e).at(self.splitter.location().clone()))?;
+            if read == 0 {
+                match self.splitter.finish()? {
+                    None => {
+                        self.trailer = self.preambles.take_trailer(&self.decoder);
+                        return Ok(None);
+                    }
+                    Some(location) => {
+                        self.recovered = true;
+                        break (location, self.buffer.len(), self.buffer.len());
+                    }
+                }
+            }
+            if let Err(e) = self.decoder.push_line(&self.buffer[line_start..]) {
+                // The rest of the input cannot be decoded either.
+                self.done = true;
+                return Err(e.at(self.splitter.location().clone()));
+            }
+            let line = self.splitter.strip_bom(&self.buffer[line_start..]);
+            let bom = read - line.len();
+            match self.splitter.push_line(line) {
+                LineKind::Outside => {
+                    self.preambles.push_line(line);
+                    self.buffer.clear();
+                }
+                LineKind::Start => {
+                    self.buffer.drain(..bom);
+                    self.preamble = self.preambles.take_preamble(&self.decoder);
+                }
+                LineKind::Inside => {}
+                LineKind::End(location) => break (location, self.buffer.len(), self.buffer.len()),
+                LineKind::Restart(location) => {
+                    self.recovered = true;
+                    break (location, line_start, line_start);
+                }
+                LineKind::Recovered(location, blank) => {
+                    self.recovered = true;
+                    self.preambles
+                        .push_line(&self.buffer[(line_start - blank)..]);
+                    break (location, line_start - blank, self.buffer.len());
+                }
+            }
+        };
+        let result = self.parser.parse_owned_reference(
+            trim_line_endings(&self.buffer[..end]),
+            &location,
+            &self.decoder,
+        );
+        self.buffer.drain(..next);
+        let (reference, warnings, extras) = result?;
+        self.warnings = warnings;
+        self.extras = extras;
+        Ok(Some(reference))
+    }
+}
+
+impl<'p, R: BufRead> Iterator for ReferenceReader<'p, R> {
+    type Item = PResult<HashMap<String, String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.next_reference() {
+                Ok(None) => self.done = true,
+                Ok(Some(reference)) => return Some(Ok(reference)),
+                Err(e) => {
+                    // Read errors and input that cannot be decoded are not caused by
+                    // an invalid reference, so they are never skipped.
+                    let fatal = self.done || matches!(e, Error::Io(..));
+                    if fatal || matches!(e, Error::EOF(_)) || self.parser.mode == ParseMode::Strict
+                    {
+                        self.done = true;
+                    }
+                    if !fatal && self.parser.mode == ParseMode::SkipInvalid {
+                        continue;
+                    }
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Read a line, including its line ending, and append it to `buf`.
+///
+/// Lines may end in `\n`, `\r\n` or `\r`. Returns the number of bytes read, which
+/// is 0 at the end of the input.
+fn read_line<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<usize> {
+    let start = buf.len();
+    loop {
+        let available = fill_buf(reader)?;
+        if available.is_empty() {
+            break;
+        }
+        match available.iter().position(|c| *c == b'\n' || *c == b'\r') {
+            Some(idx) => {
+                let line_ending = available[idx];
+                buf.extend_from_slice(&available[..=idx]);
+                reader.consume(idx + 1);
+                if line_ending == b'\r' && fill_buf(reader)?.first() == Some(&b'\n') {
+                    buf.push(b'\n');
+                    reader.consume(1);
+                }
+                break;
+            }
+            None => {
+                let len = available.len();
+                buf.extend_from_slice(available);
+                reader.consume(len);
+            }
+        }
+    }
+    Ok(buf.len() - start)
+}
+
+fn fill_buf<R: BufRead>(reader: &mut R) -> io::Result<&[u8]> {
+    // Retry interrupted reads, after which the buffer is returned by the final call.
+    while let Err(e) = reader.fill_buf() {
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(e);
+        }
+    }
+    reader.fill_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, Location};
+    use std::io::BufReader;
+
+    const INPUT: &[u8] = b"1.
+TY  - JOUR
+ID  - 1
+T1  - First title
+AB  - First line
+second line
+ER  - 
+
+2.
+TY  - BOOK
+ID  - 2
+ER  - 
+";
+
+    #[test]
+    fn test_read_line() {
+        let mut reader = BufReader::with_capacity(2, b"ab\r\ncd\ref\n\r\ngh".as_slice());
+        let mut buf = Vec::new();
+        let mut lines = Vec::new();
+        while read_line(&mut reader, &mut buf).unwrap() > 0 {
+            lines.push(std::mem::take(&mut buf));
+        }
+        assert_eq!(
+            lines,
+            [
+                b"ab\r\n".to_vec(),
+                b"cd\r".to_vec(),
+                b"ef\n".to_vec(),
+                b"\r\n".to_vec(),
+                b"gh".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let parser = RisParser::default();
+        let expected: Vec<HashMap<String, String>> = parser
+            .parse(INPUT)
+            .unwrap()
+            .into_iter()
+            .map(|r| {
+                r.into_iter()
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect()
+            })
+            .collect();
+
+        for capacity in [1, 2, 7, 8192] {
+            let reader = BufReader::with_capacity(capacity, INPUT);
+            let references: Vec<_> = parser.parse_reader(reader).collect::<PResult<_>>().unwrap();
+            assert_eq!(references, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_line_endings() {
+        let input =
+            b"\xef\xbb\xbfTY  - JOUR\r\nT1  - Windows\r\nER  - \r\n\rTY  - JOUR\rT1  - Mac\rER  -";
+        let parser = RisParser::default();
+        let references: Vec<_> = parser
+            .parse_reader(BufReader::with_capacity(1, input.as_slice()))
+            .collect::<PResult<_>>()
+            .unwrap();
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0]["T1  - "], "Windows");
+        assert_eq!(references[1]["T1  - "], "Mac");
+        assert_eq!(references[1].get("ER  - "), None);
+    }
+
+    #[test]
+    fn test_parse_reader_errors() {
+        let input = b"TY  - JOUR
+ER  - 
+TY  - JOUR
+T1  - \xff
+ER  - 
+TY  - JOUR
+ID  - 3
+ER  - 
+TY  - JOUR
+";
+        let parser = RisParser::default();
+        let results: Vec<_> = parser.parse_reader(input.as_slice()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        let err = results[1].as_ref().unwrap_err();
+        assert!(matches!(err, Error::InvalidUtf8(..)));
+        assert_eq!(err.location(), parser.parse(input).unwrap_err().location());
+
+        let parser = RisParser::default().with_mode(ParseMode::SkipInvalid);
+        let results: Vec<_> = parser.parse_reader(input.as_slice()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.is_ok()));
+
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let results: Vec<_> = parser.parse_reader(input.as_slice()).collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        assert_eq!(
+            results[3],
+            Err(Error::EOF(Location {
+                offset: 70,
+                line: 9,
+                column: 1,
+                reference: Some(3),
+                tag: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_reader_error_location() {
+        let input = b"1.\nTY  - JOUR\nER  - \n\n2.\nTY  - JOUR\nT1  - \xff\nER  - \n";
+        let parser = RisParser::default();
+        let expected = parser.parse(input).unwrap_err();
+        assert_eq!(expected.location().line, 7);
+        assert_eq!(expected.location().offset, 36);
+        let results: Vec<_> = parser.parse_reader(input.as_slice()).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1], Err(expected));
+    }
+
+    #[test]
+    fn test_parse_reader_encoding() {
+        let input =
+            b"TY  - JOUR\nT1  - Caf\xe9 \x93quoted\x94\nER  - \nTY  - JOUR\nT1  - \x80\nER  - \n";
+        let parser = RisParser::default();
+        let references: Vec<_> = parser
+            .parse_reader(input.as_slice())
+            .with_encoding(Some(Encoding::Windows1252))
+            .collect();
+        assert_eq!(
+            references[0].as_ref().unwrap()["T1  - "],
+            "Caf\u{e9} \u{201c}quoted\u{201d}"
+        );
+
+        // The encoding is detected once for the whole input, although the second
+        // reference on its own is valid UTF-8.
+        let input = b"TY  - JOUR\nT1  - Caf\xe9\nER  - \nTY  - JOUR\nT1  - \xc3\xa9\nER  - \n";
+        let references: Vec<_> = parser
+            .parse_reader(input.as_slice())
+            .with_encoding(None)
+            .collect::<PResult<_>>()
+            .unwrap();
+        assert_eq!(references[0]["T1  - "], "Caf\u{e9}");
+        assert_eq!(references[1]["T1  - "], "\u{c3}\u{a9}");
+
+        let references: Vec<_> = parser
+            .parse_reader(input.as_slice())
+            .with_lossy(true)
+            .collect::<PResult<_>>()
+            .unwrap();
+        assert_eq!(references[0]["T1  - "], "Caf\u{fffd}");
+        assert_eq!(references[1]["T1  - "], "\u{e9}");
+    }
+
+    #[test]
+    fn test_parse_reader_utf16() {
+        let input: Vec<u8> = "\u{feff}TY  - JOUR\r\nER  - \r\n"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        let parser = RisParser::default().with_mode(ParseMode::SkipInvalid);
+        let results: Vec<_> = parser
+            .parse_reader(input.as_slice())
+            .with_encoding(None)
+            .collect();
+        assert_eq!(results.len(), 1);
+        let err = results[0].as_ref().unwrap_err();
+        assert!(err.to_string().starts_with("UTF-16LE input cannot be read"));
+        assert_eq!(err.location().line, 1);
+    }
+
+    #[test]
+    fn test_parse_reader_preambles() {
+        let input = b"Database: Embase
+
+1.
+TY  - JOUR
+ER  - 
+
+2.
+TY  - JOUR
+T1  - \xff
+ER  - 
+end
+";
+        let parser = RisParser::default().with_mode(ParseMode::Collect);
+        let mut references = parser.parse_reader(input.as_slice());
+        assert!(references.next().unwrap().is_ok());
+        assert_eq!(references.header().get("Database"), Some("Embase"));
+        assert_eq!(references.preamble().text, "1.\n");
+        assert!(references.next().unwrap().is_err());
+        assert_eq!(references.preamble().text, "\n2.\n");
+        assert!(references.next().is_none());
+        assert_eq!(references.trailer().text, "end\n");
+    }
+
+    #[test]
+    fn test_parse_reader_io_error() {
+        struct FailingReader;
+        impl io::Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+        let parser = RisParser::default();
+        let mut references = parser.parse_reader(BufReader::new(FailingReader));
+        let err = references.next().unwrap().unwrap_err();
+        assert!(matches!(err, Error::Io(..)));
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(references.next().is_none());
+    }
+}
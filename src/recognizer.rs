@@ -0,0 +1,304 @@
+//! Recognition of RIS tags that deviate from the canonical `XX  - ` form.
+//!
+//! Vendor exports and hand-edited files contain tags such as `TY - `, `ty  - `,
+//! `TY\t- ` or an `ER  -` whose trailing space was stripped by an editor. These are
+//! recognised here and mapped to their canonical form.
+
+/// The ways in which a tag in the input differed from its canonical `XX  - ` form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TagVariant {
+    /// The tag code was not uppercase, as in `ty  - `.
+    pub lowercase: bool,
+    /// The whitespace around the dash was not the canonical two spaces before and
+    /// one space after it, as in `TY - ` or `TY\t- `.
+    pub irregular_spacing: bool,
+    /// The line ended directly after the dash, as in `ER  -`.
+    pub missing_trailing_space: bool,
+}
+
+impl TagVariant {
+    pub fn is_canonical(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A tag recognised at the start of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecognizedTag<'t> {
+    /// The canonical form of the tag.
+    pub tag: &'t [u8],
+    /// The number of bytes the tag took up in the input.
+    pub len: usize,
+    pub variant: TagVariant,
+}
+
+const ALPHABET: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The canonical form of every possible two character tag code.
+///
+/// Recognised tags refer to this table, so they can be used independently of the
+/// lifetime of the input they were found in.
+static CANONICAL_TAGS: [[u8; 6]; 36 * 36] = canonical_tags();
+
+const fn canonical_tags() -> [[u8; 6]; 36 * 36] {
+    let mut tags = [[0; 6]; 36 * 36];
+    let mut i = 0;
+    while i < tags.len() {
+        tags[i] = [ALPHABET[i / 36], ALPHABET[i % 36], b' ', b' ', b'-', b' '];
+        i += 1;
+    }
+    tags
+}
+
+fn alphabet_index(c: u8) -> usize {
+    match c.to_ascii_uppercase() {
+        c @ b'0'..=b'9' => (c - b'0') as usize,
+        c => (c - b'A') as usize + 10,
+    }
+}
+
+/// Recognise a tag at the start of `text`.
+///
+/// The code of a tag is a letter followed by a letter or a digit, so lines such as
+/// `10  - 20 mg` are not tags. Without `lenient` only the canonical form and the
+/// form without trailing space at the end of a line are accepted. With `lenient` a
+/// lowercase code, as in `ty  - `, or any spaces or tabs around the dash, as in
+/// `TY - `, are accepted too, but not both at once, so that wrapped text such as
+/// `is - not clear` is not read as a tag.
+pub(crate) fn recognize(text: &[u8], lenient: bool) -> Option<RecognizedTag<'static>> {
+    let code = [*text.first()?, *text.get(1)?];
+    if !code[0].is_ascii_alphabetic() || !code[1].is_ascii_alphanumeric() {
+        return None;
+    }
+    let mut variant = TagVariant {
+        lowercase: code.iter().any(u8::is_ascii_lowercase),
+        ..TagVariant::default()
+    };
+
+    let mut idx = 2;
+    while matches!(text.get(idx), Some(b' ' | b'\t')) {
+        idx += 1;
+    }
+    if idx == 2 || text.get(idx) != Some(&b'-') {
+        return None;
+    }
+    variant.irregular_spacing = &text[2..idx] != b"  ";
+    idx += 1;
+    match text.get(idx) {
+        Some(b' ') => idx += 1,
+        Some(b'\t') => {
+            variant.irregular_spacing = true;
+            idx += 1;
+        }
+        None | Some(b'\r' | b'\n') => variant.missing_trailing_space = true,
+        Some(_) => return None,
+    }
+
+    if variant.lowercase && variant.irregular_spacing {
+        return None;
+    }
+    if !lenient && (variant.lowercase || variant.irregular_spacing) {
+        return None;
+    }
+    Some(RecognizedTag {
+        tag: &CANONICAL_TAGS[alphabet_index(code[0]) * 36 + alphabet_index(code[1])],
+        len: idx,
+        variant,
+    })
+}
+
+/// Check if `text` starts with `tag`, or with one of its variants.
+///
+/// Returns the number of bytes the tag took up in the input and its variant.
+pub(crate) fn match_tag(text: &[u8], tag: &[u8], lenient: bool) -> Option<(usize, TagVariant)> {
+    if text.starts_with(tag) {
+        return Some((tag.len(), TagVariant::default()));
+    }
+    recognize(text, lenient)
+        .filter(|recognized| recognized.tag == tag)
+        .map(|recognized| (recognized.len, recognized.variant))
+}
+
+/// The shape of the tags of a format.
+///
+/// A line that starts with a tag of this shape, but not with one of the allowed
+/// tags, has an unknown tag, as opposed to being a continuation line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum TagPattern {
+    /// RIS tags like `TY  - `, and their variants accepted by the parser.
+    #[default]
+    Ris,
+    /// A code of `min_len` to `max_len` uppercase letters or digits, padded with
+    /// spaces to `width` bytes and followed by `separator`.
+    Padded {
+        min_len: usize,
+        max_len: usize,
+        width: usize,
+        separator: Vec<u8>,
+    },
+}
+
+impl TagPattern {
+    /// The pattern of MEDLINE (nbib) tags such as `AU  - `, `FAU - ` and `PMID- `.
+    pub fn medline() -> Self {
+        Self::Padded {
+            min_len: 2,
+            max_len: 4,
+            width: 4,
+            separator: b"- ".to_vec(),
+        }
+    }
+
+    /// Find a tag of this shape at the start of `text`.
+    ///
+    /// RIS tags are returned in their canonical form, like `recognize` does, other
+    /// tags as they are in the input.
+    pub(crate) fn find<'t>(&self, text: &'t [u8], lenient: bool) -> Option<RecognizedTag<'t>> {
+        match self {
+            Self::Ris => recognize(text, lenient),
+            Self::Padded {
+                min_len,
+                max_len,
+                width,
+                separator,
+            } => {
+                let code_len = text
+                    .iter()
+                    .take(*max_len)
+                    .take_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+                    .count();
+                let padding = text.get(code_len..*width.max(&code_len))?;
+                if code_len < *min_len || padding.iter().any(|c| *c != b' ') {
+                    return None;
+                }
+                let len = code_len.max(*width) + separator.len();
+                let tag = text.get(..len).filter(|tag| tag.ends_with(separator))?;
+                Some(RecognizedTag {
+                    tag,
+                    len,
+                    variant: TagVariant::default(),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_tags() {
+        assert_eq!(&CANONICAL_TAGS[0], b"00  - ");
+        assert_eq!(
+            &CANONICAL_TAGS[alphabet_index(b'T') * 36 + alphabet_index(b'Y')],
+            b"TY  - "
+        );
+        assert_eq!(&CANONICAL_TAGS[36 * 36 - 1], b"ZZ  - ");
+    }
+
+    #[test]
+    fn test_recognize_exact() {
+        let recognized = recognize(b"TY  - JOUR", false).unwrap();
+        assert_eq!(recognized.tag, b"TY  - ");
+        assert_eq!(recognized.len, 6);
+        assert!(recognized.variant.is_canonical());
+
+        let recognized = recognize(b"ER  -\nTY  - ", false).unwrap();
+        assert_eq!(recognized.tag, b"ER  - ");
+        assert_eq!(recognized.len, 5);
+        assert!(recognized.variant.missing_trailing_space);
+
+        assert!(recognize(b"ER  -", false).is_some());
+        assert!(recognize(b"TY - JOUR", false).is_none());
+        assert!(recognize(b"ty  - JOUR", false).is_none());
+    }
+
+    #[test]
+    fn test_recognize_lenient() {
+        for (text, len) in [
+            (b"TY - JOUR".as_slice(), 5),
+            (b"ty  - JOUR", 6),
+            (b"TY\t-\tJOUR", 5),
+            (b"TY   -  JOUR", 7),
+            (b"ty  -\r\n", 5),
+        ] {
+            let recognized = recognize(text, true).unwrap();
+            assert_eq!(recognized.tag, b"TY  - ");
+            assert_eq!(recognized.len, len);
+            assert!(!recognized.variant.is_canonical());
+        }
+        assert_eq!(
+            recognize(b"ty  -", true).unwrap().variant,
+            TagVariant {
+                lowercase: true,
+                irregular_spacing: false,
+                missing_trailing_space: true
+            }
+        );
+        // A lowercase code with irregular spacing is more likely wrapped text.
+        for text in [b"ty - JOUR".as_slice(), b"Ty\t- JOUR", b"is - not clear"] {
+            assert!(recognize(text, true).is_none());
+        }
+    }
+
+    #[test]
+    fn test_recognize_invalid() {
+        for text in [
+            b"TY".as_slice(),
+            b"TY-JOUR",
+            b"TY  -JOUR",
+            b"T!  - ",
+            b"10  - ",
+            b"1A  - ",
+            b"TYP - ",
+            b" TY  - ",
+            b"\xc2\xa9Y  - ",
+        ] {
+            assert!(recognize(text, true).is_none());
+        }
+    }
+
+    #[test]
+    fn test_match_tag() {
+        assert_eq!(
+            match_tag(b"ER  - ", b"ER  - ", false),
+            Some((6, TagVariant::default()))
+        );
+        assert_eq!(match_tag(b"ER  -", b"ER  - ", false).unwrap().0, 5);
+        assert_eq!(match_tag(b"er - ", b"ER  - ", false), None);
+        assert_eq!(match_tag(b"er  - ", b"ER  - ", true).unwrap().0, 6);
+        assert_eq!(match_tag(b"ER - ", b"ER  - ", true).unwrap().0, 5);
+        assert_eq!(match_tag(b"er - ", b"ER  - ", true), None);
+        assert_eq!(match_tag(b"TY  -", b"ER  - ", true), None);
+        assert_eq!(match_tag(b"foobar", b"foo", false).unwrap().0, 3);
+    }
+
+    #[test]
+    fn test_pattern() {
+        let medline = TagPattern::medline();
+        assert_eq!(
+            medline.find(b"AU  - Marx K", false).map(|r| r.tag),
+            Some(&b"AU  - "[..])
+        );
+        assert_eq!(
+            medline.find(b"FAU - Marx, Karl", false).map(|r| r.tag),
+            Some(&b"FAU - "[..])
+        );
+        assert_eq!(
+            medline.find(b"PMID- 12345", false).map(|r| r.tag),
+            Some(&b"PMID- "[..])
+        );
+        assert_eq!(medline.find(b"A   - too short", false), None);
+        assert_eq!(medline.find(b"au  - lowercase", false), None);
+        assert_eq!(medline.find(b"AU - narrow", false), None);
+        assert_eq!(medline.find(b"ABCDE- too long", false), None);
+
+        assert_eq!(
+            TagPattern::Ris.find(b"ty  - JOUR", true).map(|r| r.tag),
+            Some(&b"TY  - "[..])
+        );
+        assert_eq!(TagPattern::Ris.find(b"ty  - JOUR", false), None);
+        assert_eq!(TagPattern::Ris.find(b"10  - 20 mg", true), None);
+    }
+}
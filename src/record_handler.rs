@@ -0,0 +1,137 @@
+use crate::tag_handler::TagHandler;
+use crate::Handler;
+use crate::PResult;
+use crate::Tag;
+use crate::TagSet;
+
+/// A reference with its fields in input order.
+///
+/// Unlike the maps returned by `RisParser::parse`, a record keeps every value of
+/// repeated tags and the order of the fields, so it can be written back as it was
+/// read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record<'b> {
+    pub fields: Vec<(Tag, &'b str)>,
+}
+
+impl<'b> Record<'b> {
+    /// The last value of `tag`, like the value `RisParser::parse` keeps.
+    pub fn get(&self, tag: &Tag) -> Option<&'b str> {
+        self.get_all(tag).last()
+    }
+
+    /// The first value of `tag`.
+    pub fn first(&self, tag: &Tag) -> Option<&'b str> {
+        self.get_all(tag).next()
+    }
+
+    /// Every value of `tag`, in input order.
+    pub fn get_all<'r>(&'r self, tag: &'r Tag) -> impl DoubleEndedIterator<Item = &'b str> + 'r {
+        self.fields
+            .iter()
+            .filter(move |(field_tag, _)| field_tag == tag)
+            .map(|(_, content)| *content)
+    }
+
+    pub fn contains(&self, tag: &Tag) -> bool {
+        self.fields.iter().any(|(field_tag, _)| field_tag == tag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Tag, &'b str)> {
+        self.fields.iter().map(|(tag, content)| (tag, *content))
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl<'b> From<Vec<(Tag, &'b str)>> for Record<'b> {
+    fn from(fields: Vec<(Tag, &'b str)>) -> Self {
+        Self { fields }
+    }
+}
+
+impl<'b> IntoIterator for Record<'b> {
+    type Item = (Tag, &'b str);
+    type IntoIter = std::vec::IntoIter<(Tag, &'b str)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.into_iter()
+    }
+}
+
+/// Handler that collects the fields of a reference into a `Record`.
+#[derive(Debug, Clone)]
+pub struct RecordHandler<'a, 'b> {
+    handler: TagHandler<'a, 'b>,
+}
+
+impl<'a, 'b> RecordHandler<'a, 'b> {
+    pub fn new(handler: TagHandler<'a, 'b>) -> Self {
+        Self { handler }
+    }
+}
+
+impl<'a, 'b> Handler<'a, 'b, &'b str, Record<'b>> for RecordHandler<'a, 'b> {
+    fn start_tag(&self) -> &'a [u8] {
+        self.handler.start_tag()
+    }
+
+    fn end_tag(&self) -> &'a [u8] {
+        self.handler.end_tag()
+    }
+
+    fn allowed_tags(&self) -> &'a TagSet {
+        self.handler.allowed_tags()
+    }
+
+    fn handle(&mut self, tag: &'b [u8], content: &'b str) -> PResult<()> {
+        self.handler.handle(tag, content)
+    }
+
+    fn handle_unknown(&mut self, tag: &'b [u8], content: &'b str) -> PResult<()> {
+        self.handler.handle_unknown(tag, content)
+    }
+
+    fn finish(self) -> Record<'b> {
+        self.handler.finish().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_handler() {
+        let allowed_tags = TagSet::from_iter([b"TY  - ", b"AU  - ", b"T1  - ", b"ER  - "]);
+        let mut handler = RecordHandler::new(TagHandler::new(b"TY  - ", b"ER  - ", &allowed_tags));
+        handler.handle(b"TY  - ", "JOUR").unwrap();
+        handler.handle(b"AU  - ", "Marx, Karl").unwrap();
+        handler.handle(b"T1  - ", "Title").unwrap();
+        handler.handle(b"AU  - ", "Lindgren, Astrid").unwrap();
+        handler.handle(b"ER  - ", "").unwrap();
+
+        let record = handler.finish();
+        assert_eq!(record.len(), 4);
+        assert_eq!(record.fields[2], (Tag::PrimaryTitle, "Title"));
+        assert_eq!(record.first(&Tag::Author), Some("Marx, Karl"));
+        assert_eq!(record.get(&Tag::Author), Some("Lindgren, Astrid"));
+        assert_eq!(
+            record.get_all(&Tag::Author).collect::<Vec<_>>(),
+            ["Marx, Karl", "Lindgren, Astrid"]
+        );
+        assert_eq!(record.get(&Tag::Doi), None);
+        assert!(record.contains(&Tag::Type));
+        let tags: Vec<&Tag> = record.iter().map(|(tag, _)| tag).collect();
+        assert_eq!(
+            tags,
+            [&Tag::Type, &Tag::Author, &Tag::PrimaryTitle, &Tag::Author]
+        );
+    }
+}
@@ -1,8 +1,15 @@
 use std::iter::Enumerate;
 
+use crate::error::Location;
+use crate::parser::BoundaryRecovery;
+use crate::recognizer::match_tag;
+use crate::utils::{trim_line_ending, trim_line_endings};
 use crate::Error;
 use crate::PResult;
 
+const BOM: &[u8] = "\u{feff}".as_bytes();
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TakeTagResult {
     Present(usize),
@@ -17,40 +24,139 @@ pub struct ReferenceIterator<'a, 'b> {
     end_tag: &'a [u8],
     text: &'b [u8],
     cursor: Enumerate<std::slice::Iter<'b, u8>>,
+    /// Number of bytes stripped from the start of the input.
+    offset: usize,
+    /// Line number of the cursor.
+    line: usize,
+    /// Offset in the input of the start of the current line.
+    line_start: usize,
+    /// Number of references found so far.
+    count: usize,
+    /// Location of the start of the last reference.
+    location: Location,
+    /// Text that was skipped before the last reference.
+    skipped: &'b [u8],
+    /// Index in the text of the end of the last reference, including its line ending.
+    reference_end: usize,
+    recovery: BoundaryRecovery,
+    /// Whether the end of the last reference was inferred.
+    recovered: bool,
+    lenient_tags: bool,
 }
 
 impl<'a, 'b> ReferenceIterator<'a, 'b> {
     pub fn new(start_tag: &'a [u8], end_tag: &'a [u8], text: &'b [u8]) -> Self {
-        let text_without_bom: &[u8];
-        if &text[..3] == "\u{feff}".as_bytes() {
-            text_without_bom = &text[3..]
-        } else {
-            text_without_bom = text
-        }
+        let text_without_bom = text.strip_prefix(BOM).unwrap_or(text);
         ReferenceIterator {
             start_tag,
             end_tag,
             text: text_without_bom,
             cursor: text_without_bom.iter().enumerate(),
+            offset: text.len() - text_without_bom.len(),
+            line: 1,
+            line_start: 0,
+            count: 0,
+            location: Location::start(),
+            skipped: &[],
+            reference_end: 0,
+            recovery: BoundaryRecovery::default(),
+            recovered: false,
+            lenient_tags: false,
         }
     }
 
+    /// Also accept start and end tags in other case and with other spacing.
+    ///
+    /// Tags without their trailing space at the end of a line, such as an `ER  -`
+    /// stripped by an editor, are always accepted.
+    pub fn with_lenient_tags(mut self, lenient_tags: bool) -> Self {
+        self.lenient_tags = lenient_tags;
+        self
+    }
+
+    /// End references without end tag, see `BoundaryRecovery`.
+    pub fn with_boundary_recovery(mut self, recovery: BoundaryRecovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
     pub fn default(text: &'b [u8]) -> Self {
         ReferenceIterator::new("TY  - ".as_bytes(), "ER  - ".as_bytes(), text)
     }
 
-    /// Move the cursor to the next newline character and return its index.
+    /// Location of the start tag of the reference that was last returned.
+    ///
+    /// If the last call to `next` returned an error, this is the location of the
+    /// reference in which the error occurred.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Text outside of the references that was skipped before the reference that was
+    /// last returned, such as a header or a line that numbers the reference.
+    ///
+    /// After the last reference this is the text after it. A byte order mark is
+    /// not included.
+    pub fn skipped(&self) -> &'b [u8] {
+        self.skipped
+    }
+
+    /// Whether the end of the reference that was last returned was inferred, because
+    /// it has no end tag.
+    pub fn recovered(&self) -> bool {
+        self.recovered
+    }
+
+    /// End the reference that started at `start_idx` at `end_idx` without end tag.
+    ///
+    /// Line endings before `end_idx` are left out of the reference, the text from
+    /// `end_idx` on is searched for the next reference.
+    fn recover(&mut self, start_idx: usize, end_idx: usize) -> &'b [u8] {
+        self.recovered = true;
+        self.reference_end = end_idx;
+        trim_line_endings(&self.text[start_idx..end_idx])
+    }
+
+    /// Move the cursor to the start of the next line and return the index at which the
+    /// line ending starts.
     fn take_line(&mut self) -> Option<usize> {
         loop {
             let (idx, c) = self.cursor.next()?;
-            if *c == b'\n' {
+            if self.take_line_ending(idx, *c) {
                 return Some(idx);
             }
         }
     }
 
+    /// Check if the character `c` at `idx`, which was just taken from the cursor,
+    /// starts a line ending.
+    ///
+    /// Lines may end in `\n`, `\r\n` or `\r`. If `c` starts a line ending, the cursor
+    /// is moved past the full line ending.
+    fn take_line_ending(&mut self, idx: usize, c: u8) -> bool {
+        match c {
+            b'\n' => self.new_line(idx + 1),
+            b'\r' => {
+                if self.text.get(idx + 1) == Some(&b'\n') {
+                    self.cursor.next();
+                    self.new_line(idx + 2);
+                } else {
+                    self.new_line(idx + 1);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Register that the cursor moved to a new line, starting at `idx`.
+    fn new_line(&mut self, idx: usize) {
+        self.line += 1;
+        self.line_start = self.offset + idx;
+    }
+
     /// Check if the tag occurs at the current position.
-    /// 
+    ///
     /// Returns
     /// -------
     /// TakeTagResult::EOF
@@ -63,15 +169,23 @@ impl<'a, 'b> ReferenceIterator<'a, 'b> {
     ///     If the line does start with the tag. The cursor will be at the first byte
     ///     that was not part of the tag.
     /// TakeTagResult::Present(idx)
-    ///     If the tag is present. The value of idx is the index of the start of the
-    ///     tag in the text.
+    ///     If the tag, or a variant of it, is present. The value of idx is the index
+    ///     of the start of the tag in the text.
     fn take_tag(&mut self, tag: &[u8]) -> TakeTagResult {
+        let position = self.text.len() - self.cursor.len();
+        if let Some((len, _)) = match_tag(&self.text[position..], tag, self.lenient_tags) {
+            if len > 0 {
+                self.cursor.nth(len - 1);
+            }
+            return TakeTagResult::Present(position);
+        }
+
         let mut idx: usize = 0;
         for c in tag.iter() {
             match self.cursor.next() {
                 None => return TakeTagResult::EOF,
                 Some((current_idx, current_char)) => {
-                    if *current_char == b'\n' {
+                    if self.take_line_ending(current_idx, *current_char) {
                         return TakeTagResult::NewLine;
                     }
                     if current_char != c {
@@ -94,37 +208,238 @@ impl<'a, 'b> Iterator for ReferenceIterator<'a, 'b> {
         let start_idx;
         loop {
             match self.take_tag(self.start_tag) {
-                TakeTagResult::EOF => return None,
+                TakeTagResult::EOF => {
+                    self.skipped = &self.text[self.reference_end..];
+                    return None;
+                }
                 TakeTagResult::NotPresent => {
                     self.take_line();
                 }
                 TakeTagResult::NewLine => {}
                 TakeTagResult::Present(idx) => {
                     start_idx = idx;
+                    self.skipped = &self.text[self.reference_end..idx];
+                    self.location = Location {
+                        offset: self.offset + idx,
+                        line: self.line,
+                        column: self.offset + idx - self.line_start + 1,
+                        reference: Some(self.count),
+                        tag: None,
+                    };
+                    self.count += 1;
                     break;
                 }
             }
         }
         // Parsing to end tag
+        self.recovered = false;
+        let mut line_start = false;
+        let mut blank_lines = 0;
+        let mut blank_start = 0;
         loop {
+            if line_start && self.recovery != BoundaryRecovery::Off {
+                let position = self.text.len() - self.cursor.len();
+                let rest = &self.text[position..];
+                if match_tag(rest, self.start_tag, self.lenient_tags).is_some() {
+                    return Some(Ok(self.recover(start_idx, position)));
+                }
+                if let BoundaryRecovery::BlankLines(max_blank_lines) = self.recovery {
+                    let line = rest.split(|c| *c == b'\n' || *c == b'\r').next();
+                    if line.is_some_and(|line| line.iter().all(|c| *c == b' ' || *c == b'\t')) {
+                        if blank_lines == 0 {
+                            blank_start = position;
+                        }
+                        blank_lines += 1;
+                        if blank_lines >= max_blank_lines.get() {
+                            self.take_line();
+                            return Some(Ok(self.recover(start_idx, blank_start)));
+                        }
+                    } else {
+                        blank_lines = 0;
+                    }
+                }
+            }
+            line_start = true;
             match self.take_tag(self.end_tag) {
-                TakeTagResult::EOF => return Some(Err(Error::EOF)),
+                TakeTagResult::EOF if self.recovery != BoundaryRecovery::Off => {
+                    return Some(Ok(self.recover(start_idx, self.text.len())));
+                }
+                TakeTagResult::EOF => {
+                    self.reference_end = self.text.len();
+                    return Some(Err(Error::EOF(self.location.clone())));
+                }
                 TakeTagResult::NotPresent => {
                     self.take_line();
                 }
                 TakeTagResult::NewLine => {}
-                TakeTagResult::Present(_) => match self.take_line() {
-                    None => return Some(Ok(&self.text[start_idx..])),
-                    Some(end_idx) => return Some(Ok(&self.text[start_idx..end_idx])),
-                },
+                TakeTagResult::Present(_) => {
+                    let end_idx = self.take_line();
+                    self.reference_end = self.text.len() - self.cursor.len();
+                    return Some(Ok(&self.text[start_idx..end_idx.unwrap_or(self.text.len())]));
+                }
+            }
+        }
+    }
+}
+
+/// What a line is, relative to the references in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LineKind {
+    /// The line is not part of a reference.
+    Outside,
+    /// The line starts a reference.
+    Start,
+    /// The line is part of a reference.
+    Inside,
+    /// The line ends the reference that started at the given location.
+    End(Location),
+    /// The reference that started at the given location has no end tag, and is
+    /// ended by recovery before this line, which starts the next reference.
+    Restart(Location),
+    /// The reference that started at the given location has no end tag, and is
+    /// ended by recovery at a run of blank lines. The blank lines, this line and
+    /// the given number of bytes of the lines before it, are not part of the
+    /// reference.
+    Recovered(Location, usize),
+}
+
+/// Splits input that arrives line by line into references.
+///
+/// This is the counterpart of `ReferenceIterator` for input that is not available as
+/// a single slice. Start and end tags are recognised in the same way, references
+/// without end tag are recovered in the same way, and locations are tracked in the
+/// same way.
+#[derive(Debug, Clone)]
+pub(crate) struct LineSplitter<'a> {
+    start_tag: &'a [u8],
+    end_tag: &'a [u8],
+    lenient_tags: bool,
+    recovery: BoundaryRecovery,
+    /// Location of the start of the next line.
+    location: Location,
+    /// Number of references found so far.
+    count: usize,
+    /// Location of the start of the current reference.
+    start: Option<Location>,
+    /// Number of consecutive blank lines at the end of the current reference.
+    blank_lines: usize,
+    /// Number of bytes of these blank lines.
+    blank_len: usize,
+}
+
+impl<'a> LineSplitter<'a> {
+    pub(crate) fn new(start_tag: &'a [u8], end_tag: &'a [u8], lenient_tags: bool) -> Self {
+        Self {
+            start_tag,
+            end_tag,
+            lenient_tags,
+            recovery: BoundaryRecovery::default(),
+            location: Location::start(),
+            count: 0,
+            start: None,
+            blank_lines: 0,
+            blank_len: 0,
+        }
+    }
+
+    /// End references without end tag, see `BoundaryRecovery`.
+    pub(crate) fn with_boundary_recovery(mut self, recovery: BoundaryRecovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Location of the start of the next line.
+    pub(crate) fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Remove the byte order mark from the first line of the input.
+    pub(crate) fn strip_bom<'l>(&mut self, line: &'l [u8]) -> &'l [u8] {
+        match line.strip_prefix(BOM) {
+            Some(rest) if self.location.offset == 0 => {
+                self.location = self.location.advance(BOM);
+                rest
+            }
+            _ => line,
+        }
+    }
+
+    /// Register the next line, including its line ending.
+    pub(crate) fn push_line(&mut self, line: &[u8]) -> LineKind {
+        let is_start = match_tag(line, self.start_tag, self.lenient_tags).is_some();
+        let kind = match self.start.take() {
+            None if is_start => {
+                self.start = Some(self.next_start());
+                LineKind::Start
+            }
+            None => LineKind::Outside,
+            Some(start) if self.recovery != BoundaryRecovery::Off && is_start => {
+                self.start = Some(self.next_start());
+                LineKind::Restart(start)
             }
+            Some(start) if match_tag(line, self.end_tag, self.lenient_tags).is_some() => {
+                LineKind::End(start)
+            }
+            Some(start) => match self.recovery {
+                BoundaryRecovery::BlankLines(max_blank_lines) if is_blank(line) => {
+                    self.blank_lines += 1;
+                    if self.blank_lines >= max_blank_lines.get() {
+                        LineKind::Recovered(start, self.blank_len)
+                    } else {
+                        self.blank_len += line.len();
+                        self.start = Some(start);
+                        LineKind::Inside
+                    }
+                }
+                _ => {
+                    self.start = Some(start);
+                    self.blank_lines = 0;
+                    self.blank_len = 0;
+                    LineKind::Inside
+                }
+            },
+        };
+        if self.blank_lines > 0 && kind != LineKind::Inside {
+            self.blank_lines = 0;
+            self.blank_len = 0;
+        }
+        self.location = self.location.advance(line);
+        kind
+    }
+
+    /// Signal the end of the input.
+    ///
+    /// Returns the location of the start of the reference that was not ended by its
+    /// end tag, which is ended by recovery if it is enabled and results in
+    /// `Error::EOF` otherwise.
+    pub(crate) fn finish(&mut self) -> PResult<Option<Location>> {
+        match self.start.take() {
+            Some(start) if self.recovery == BoundaryRecovery::Off => Err(Error::EOF(start)),
+            start => Ok(start),
+        }
+    }
+
+    /// The location of a reference that starts at the next line.
+    fn next_start(&mut self) -> Location {
+        self.count += 1;
+        Location {
+            reference: Some(self.count - 1),
+            ..self.location.clone()
         }
     }
 }
 
+/// Check if a line is empty or holds only spaces and tabs.
+fn is_blank(line: &[u8]) -> bool {
+    trim_line_ending(line)
+        .iter()
+        .all(|c| *c == b' ' || *c == b'\t')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::num::NonZeroUsize;
 
     #[test]
     fn test_take_line() {
@@ -258,7 +573,7 @@ ER  -
 TY  - JOUR
 ER  - 
 ";
-        let mut ref_iter = ReferenceIterator::default(&ref_string.as_bytes());
+        let mut ref_iter = ReferenceIterator::default(ref_string.as_bytes());
         let first_ref = ref_iter.next().unwrap().unwrap();
         assert_eq!(first_ref.iter().next(), Some(&b'T'));
         let second_ref = ref_iter.next().unwrap().unwrap();
@@ -283,4 +598,175 @@ ER  - "
         assert_eq!(ref_iter.next(), Some(Ok(&ref_string[..])));
         assert!(ref_iter.next().is_none());
     }
+
+    #[test]
+    fn test_location() {
+        let ref_string = b"1.\nTY  - JOUR\nER  - \n\n2.\nTY  - JOUR\nID  - 1\n";
+        let mut ref_iter = ReferenceIterator::default(ref_string);
+        ref_iter.next();
+        assert_eq!(
+            ref_iter.location(),
+            &Location {
+                offset: 3,
+                line: 2,
+                column: 1,
+                reference: Some(0),
+                tag: None
+            }
+        );
+        let err = ref_iter.next().unwrap().unwrap_err();
+        let expected = Location {
+            offset: 25,
+            line: 6,
+            column: 1,
+            reference: Some(1),
+            tag: None,
+        };
+        assert_eq!(ref_iter.location(), &expected);
+        assert_eq!(err, Error::EOF(expected));
+    }
+
+    #[test]
+    fn test_location_with_bom() {
+        let ref_string = "\u{feff}TY  - \nER  - ".as_bytes();
+        let mut ref_iter = ReferenceIterator::default(ref_string);
+        ref_iter.next();
+        assert_eq!(ref_iter.location().offset, 3);
+        assert_eq!(ref_iter.location().column, 4);
+    }
+
+    #[test]
+    fn test_take_line_endings() {
+        let mut ref_iter = ReferenceIterator::default(b"a\r\nbcd\regf\n\r\nh");
+        assert_eq!(ref_iter.take_line(), Some(1));
+        assert_eq!(ref_iter.take_line(), Some(6));
+        assert_eq!(ref_iter.take_line(), Some(10));
+        assert_eq!(ref_iter.take_line(), Some(11));
+        assert_eq!(ref_iter.cursor.next(), Some((13, &b'h')));
+        assert_eq!(ref_iter.line, 5);
+    }
+
+    #[test]
+    fn test_crlf() {
+        let ref_string =
+            b"1.\r\nTY  - JOUR\r\nID  - 12345\r\nER  - \r\n\r\n2.\r\nTY  - BOOK\r\nER  - \r\n";
+        let mut ref_iter = ReferenceIterator::default(ref_string);
+        assert_eq!(
+            ref_iter.next(),
+            Some(Ok(b"TY  - JOUR\r\nID  - 12345\r\nER  - ".as_slice()))
+        );
+        assert_eq!(
+            ref_iter.next(),
+            Some(Ok(b"TY  - BOOK\r\nER  - ".as_slice()))
+        );
+        assert_eq!(ref_iter.location().line, 7);
+        assert_eq!(ref_iter.next(), None);
+    }
+
+    #[test]
+    fn test_cr() {
+        let ref_string = b"TY  - JOUR\rID  - 12345\rER  - \r\rTY  - BOOK\rER  - \r";
+        let mut ref_iter = ReferenceIterator::default(ref_string);
+        assert_eq!(
+            ref_iter.next(),
+            Some(Ok(b"TY  - JOUR\rID  - 12345\rER  - ".as_slice()))
+        );
+        assert_eq!(ref_iter.next(), Some(Ok(b"TY  - BOOK\rER  - ".as_slice())));
+        assert_eq!(ref_iter.location().line, 5);
+        assert_eq!(ref_iter.next(), None);
+    }
+
+    #[test]
+    fn test_stripped_end_tag() {
+        let ref_string = b"TY  - JOUR\nER  -\n\nTY  - BOOK\nER  -";
+        let mut ref_iter = ReferenceIterator::default(ref_string);
+        assert_eq!(ref_iter.next(), Some(Ok(b"TY  - JOUR\nER  -".as_slice())));
+        assert_eq!(ref_iter.next(), Some(Ok(b"TY  - BOOK\nER  -".as_slice())));
+        assert_eq!(ref_iter.next(), None);
+    }
+
+    #[test]
+    fn test_lenient_tags() {
+        let ref_string = b"ty  - JOUR\nER\t-\n\nTY  - BOOK\ner  - \n";
+        let mut ref_iter = ReferenceIterator::default(ref_string);
+        assert!(matches!(ref_iter.next(), Some(Err(Error::EOF(_)))));
+
+        let mut ref_iter = ReferenceIterator::default(ref_string).with_lenient_tags(true);
+        assert_eq!(ref_iter.next(), Some(Ok(b"ty  - JOUR\nER\t-".as_slice())));
+        assert_eq!(ref_iter.next(), Some(Ok(b"TY  - BOOK\ner  - ".as_slice())));
+        assert_eq!(ref_iter.location().line, 4);
+        assert_eq!(ref_iter.next(), None);
+    }
+
+    #[test]
+    fn test_line_splitter() {
+        let mut splitter = LineSplitter::new(b"TY  - ", b"ER  - ", false);
+        let line = splitter.strip_bom("\u{feff}1.\r\n".as_bytes());
+        assert_eq!(line, b"1.\r\n");
+        assert_eq!(splitter.push_line(line), LineKind::Outside);
+        assert_eq!(splitter.push_line(b"TY  - JOUR\r\n"), LineKind::Start);
+        assert_eq!(splitter.push_line(b"ID  - 1\r\n"), LineKind::Inside);
+        assert_eq!(
+            splitter.push_line(b"ER  -\r\n"),
+            LineKind::End(Location {
+                offset: 7,
+                line: 2,
+                column: 1,
+                reference: Some(0),
+                tag: None,
+            })
+        );
+        assert_eq!(splitter.push_line(b"TY  - BOOK\r\n"), LineKind::Start);
+        assert_eq!(splitter.location().line, 6);
+        let err = splitter.finish().unwrap_err();
+        assert_eq!(err.location().reference, Some(1));
+        assert_eq!(splitter.finish(), Ok(None));
+    }
+
+    #[test]
+    fn test_line_splitter_recovery() {
+        let mut splitter = LineSplitter::new(b"TY  - ", b"ER  - ", false)
+            .with_boundary_recovery(BoundaryRecovery::StartTag);
+        assert_eq!(splitter.push_line(b"TY  - JOUR\n"), LineKind::Start);
+        assert_eq!(splitter.push_line(b"\n"), LineKind::Inside);
+        assert!(matches!(
+            splitter.push_line(b"TY  - BOOK\n"),
+            LineKind::Restart(Location { line: 1, .. })
+        ));
+        assert_eq!(splitter.finish().unwrap().unwrap().line, 3);
+
+        let blank_lines = BoundaryRecovery::BlankLines(NonZeroUsize::new(2).unwrap());
+        let mut splitter =
+            LineSplitter::new(b"TY  - ", b"ER  - ", false).with_boundary_recovery(blank_lines);
+        assert_eq!(splitter.push_line(b"TY  - JOUR\n"), LineKind::Start);
+        assert_eq!(splitter.push_line(b" \r\n"), LineKind::Inside);
+        assert_eq!(splitter.push_line(b"AB  - text\n"), LineKind::Inside);
+        assert_eq!(splitter.push_line(b" \r\n"), LineKind::Inside);
+        assert!(matches!(
+            splitter.push_line(b"\n"),
+            LineKind::Recovered(Location { line: 1, .. }, 3)
+        ));
+        assert_eq!(splitter.push_line(b"\n"), LineKind::Outside);
+        assert_eq!(splitter.finish(), Ok(None));
+    }
+
+    #[test]
+    fn test_skipped() {
+        let text =
+            b"\xef\xbb\xbfHeader\n\n1.\nTY  - JOUR\nER  - \n2.\r\nTY  - BOOK\nER  - \ntrailer";
+        let mut ref_iter = ReferenceIterator::default(text);
+        assert_eq!(ref_iter.next(), Some(Ok(&b"TY  - JOUR\nER  - "[..])));
+        assert_eq!(ref_iter.skipped(), b"Header\n\n1.\n");
+        assert!(ref_iter.next().is_some());
+        assert_eq!(ref_iter.skipped(), b"2.\r\n");
+        assert_eq!(ref_iter.next(), None);
+        assert_eq!(ref_iter.skipped(), b"trailer");
+        assert_eq!(ref_iter.next(), None);
+        assert_eq!(ref_iter.skipped(), b"trailer");
+
+        let mut ref_iter = ReferenceIterator::default(b"TY  - JOUR\nER  - ");
+        assert!(ref_iter.next().is_some());
+        assert_eq!(ref_iter.next(), None);
+        assert_eq!(ref_iter.skipped(), b"");
+    }
 }
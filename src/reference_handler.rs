@@ -0,0 +1,333 @@
+use crate::author::Author;
+use crate::date::RisDate;
+use crate::identifiers::Identifier;
+use crate::pages::PageRange;
+use crate::record_handler::{Record, RecordHandler};
+use crate::Handler;
+use crate::PResult;
+use crate::ReferenceType;
+use crate::Tag;
+use crate::TagSet;
+
+/// Tags that hold the title, in order of preference.
+const TITLE_TAGS: [Tag; 2] = [Tag::PrimaryTitle, Tag::Title];
+/// Tags that hold an author.
+const AUTHOR_TAGS: [Tag; 2] = [Tag::Author, Tag::PrimaryAuthor];
+/// Tags that hold the year of publication, in order of preference.
+const YEAR_TAGS: [Tag; 2] = [Tag::PublicationYear, Tag::PrimaryDate];
+/// Tags that hold the name of the journal, in order of preference.
+const JOURNAL_TAGS: [Tag; 4] = [
+    Tag::JournalName,
+    Tag::JournalFullName,
+    Tag::JournalAbbreviation,
+    Tag::SecondaryTitle,
+];
+/// Tags that hold the abstract, in order of preference.
+const ABSTRACT_TAGS: [Tag; 2] = [Tag::Abstract, Tag::AlternateAbstract];
+/// Tags that hold the database the reference was exported from.
+const DATABASE_TAGS: [Tag; 2] = [Tag::DatabaseName, Tag::DatabaseProvider];
+
+/// A reference with typed accessors for the common bibliographic fields.
+///
+/// RIS exporters disagree on which tag to use for a field, the accessors therefore
+/// look at all the synonyms of a field, such as `T1` and `TI` for the title. The
+/// fields as they were read are available with `record`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reference<'b> {
+    record: Record<'b>,
+}
+
+impl<'b> Reference<'b> {
+    pub fn new(record: Record<'b>) -> Self {
+        Self { record }
+    }
+
+    /// The untyped fields of the reference, in input order.
+    pub fn record(&self) -> &Record<'b> {
+        &self.record
+    }
+
+    pub fn into_record(self) -> Record<'b> {
+        self.record
+    }
+
+    /// The value of the `TY` tag.
+    pub fn type_code(&self) -> Option<&'b str> {
+        self.record.first(&Tag::Type)
+    }
+
+    /// The type of the reference, from the `TY` tag.
+    pub fn reference_type(&self) -> Option<ReferenceType> {
+        self.type_code()
+            .map(|code| ReferenceType::from_code(code.trim()))
+    }
+
+    /// The title, from `T1` or else `TI`.
+    pub fn title(&self) -> Option<&'b str> {
+        self.first_of(&TITLE_TAGS)
+    }
+
+    /// The authors from `AU` and `A1`, in input order. Empty names are left out.
+    pub fn authors(&self) -> Vec<Author> {
+        self.author_names()
+            .into_iter()
+            .filter_map(|name| Author::parse(name).ok())
+            .collect()
+    }
+
+    /// The names of the authors as they were written, see `authors`.
+    pub fn author_names(&self) -> Vec<&'b str> {
+        self.all_of(&AUTHOR_TAGS)
+    }
+
+    /// The year of publication, from `PY` or else `Y1`.
+    ///
+    /// Both tags may hold a full date such as `2019/05/01/`, only the leading year
+    /// is returned. `None` if the field is missing or does not start with a year.
+    pub fn year(&self) -> Option<i32> {
+        let value = self.first_of(&YEAR_TAGS)?.trim();
+        let digits = value.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        value[..digits].parse().ok()
+    }
+
+    /// The date of publication, from `PY` or else `Y1`. `None` if the field is
+    /// missing or is not a valid date.
+    pub fn publication_date(&self) -> Option<RisDate> {
+        self.first_of(&YEAR_TAGS)
+            .and_then(|value| RisDate::parse(value).ok())
+    }
+
+    /// The date from `DA`, see `publication_date`.
+    pub fn date(&self) -> Option<RisDate> {
+        self.record
+            .first(&Tag::Date)
+            .and_then(|value| RisDate::parse(value).ok())
+    }
+
+    /// The date the work was accessed, from `Y2`, see `publication_date`.
+    pub fn access_date(&self) -> Option<RisDate> {
+        self.record
+            .first(&Tag::AccessDate)
+            .and_then(|value| RisDate::parse(value).ok())
+    }
+
+    /// The name of the journal, from the first of `JO`, `JF`, `JA` and `T2` that is
+    /// present.
+    pub fn journal(&self) -> Option<&'b str> {
+        self.first_of(&JOURNAL_TAGS)
+    }
+
+    pub fn volume(&self) -> Option<&'b str> {
+        self.record.first(&Tag::Volume)
+    }
+
+    pub fn issue(&self) -> Option<&'b str> {
+        self.record.first(&Tag::Issue)
+    }
+
+    pub fn start_page(&self) -> Option<&'b str> {
+        self.record.first(&Tag::StartPage)
+    }
+
+    pub fn end_page(&self) -> Option<&'b str> {
+        self.record.first(&Tag::EndPage)
+    }
+
+    /// The pages, from `SP` and `EP`. `None` if `SP` is missing or empty.
+    pub fn pages(&self) -> Option<PageRange> {
+        let start = self.start_page()?;
+        PageRange::parse(start, self.end_page()).ok()
+    }
+
+    pub fn doi(&self) -> Option<&'b str> {
+        self.record.first(&Tag::Doi)
+    }
+
+    /// The identifiers of the reference, in input order and without duplicates,
+    /// see `Identifier::extract`.
+    ///
+    /// If `DB` or `DP` names PubMed or MEDLINE, a number in `AN` is taken to be the
+    /// PubMed ID, see `Identifier::extract_pubmed`.
+    pub fn identifiers(&self) -> Vec<Identifier> {
+        let extract = if self.is_from_pubmed() {
+            Identifier::extract_pubmed
+        } else {
+            Identifier::extract
+        };
+        let mut identifiers: Vec<Identifier> = Vec::new();
+        for (tag, content) in self.record.iter() {
+            for identifier in extract(tag, content) {
+                if !identifiers.contains(&identifier) {
+                    identifiers.push(identifier);
+                }
+            }
+        }
+        identifiers
+    }
+
+    /// The abstract, from `AB` or else `N2`.
+    pub fn abstract_text(&self) -> Option<&'b str> {
+        self.first_of(&ABSTRACT_TAGS)
+    }
+
+    pub fn keywords(&self) -> Vec<&'b str> {
+        self.record.get_all(&Tag::Keyword).collect()
+    }
+
+    /// Whether `DB` or `DP` names PubMed or MEDLINE.
+    fn is_from_pubmed(&self) -> bool {
+        self.all_of(&DATABASE_TAGS).into_iter().any(|name| {
+            let name = name.to_ascii_lowercase();
+            name.contains("pubmed") || name.contains("medline")
+        })
+    }
+
+    /// The first value of the first tag in `tags` that is present.
+    fn first_of(&self, tags: &[Tag]) -> Option<&'b str> {
+        tags.iter().find_map(|tag| self.record.first(tag))
+    }
+
+    /// The values of all tags in `tags`, in input order.
+    fn all_of(&self, tags: &[Tag]) -> Vec<&'b str> {
+        self.record
+            .iter()
+            .filter(|(tag, _)| tags.contains(tag))
+            .map(|(_, content)| content)
+            .collect()
+    }
+}
+
+impl<'b> From<Record<'b>> for Reference<'b> {
+    fn from(record: Record<'b>) -> Self {
+        Self::new(record)
+    }
+}
+
+/// Handler that collects the fields of a reference into a `Reference`.
+#[derive(Debug, Clone)]
+pub struct ReferenceHandler<'a, 'b> {
+    handler: RecordHandler<'a, 'b>,
+}
+
+impl<'a, 'b> ReferenceHandler<'a, 'b> {
+    pub fn new(handler: RecordHandler<'a, 'b>) -> Self {
+        Self { handler }
+    }
+}
+
+impl<'a, 'b> Handler<'a, 'b, &'b str, Reference<'b>> for ReferenceHandler<'a, 'b> {
+    fn start_tag(&self) -> &'a [u8] {
+        self.handler.start_tag()
+    }
+
+    fn end_tag(&self) -> &'a [u8] {
+        self.handler.end_tag()
+    }
+
+    fn allowed_tags(&self) -> &'a TagSet {
+        self.handler.allowed_tags()
+    }
+
+    fn handle(&mut self, tag: &'b [u8], content: &'b str) -> PResult<()> {
+        self.handler.handle(tag, content)
+    }
+
+    fn handle_unknown(&mut self, tag: &'b [u8], content: &'b str) -> PResult<()> {
+        self.handler.handle_unknown(tag, content)
+    }
+
+    fn finish(self) -> Reference<'b> {
+        self.handler.finish().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synonyms() {
+        let reference = Reference::new(Record::from(vec![
+            (Tag::Type, "JOUR"),
+            (Tag::PrimaryAuthor, "Marx, Karl"),
+            (Tag::Title, "Fallback title"),
+            (Tag::Author, "Lindgren, Astrid"),
+            (Tag::PrimaryDate, "1867/09/14/"),
+            (Tag::SecondaryTitle, "Secondary"),
+            (Tag::JournalFullName, "Journal of Things"),
+            (Tag::AlternateAbstract, "Abstract"),
+            (Tag::Keyword, "a"),
+            (Tag::Keyword, "b"),
+        ]));
+        assert_eq!(reference.type_code(), Some("JOUR"));
+        assert_eq!(
+            reference.reference_type(),
+            Some(ReferenceType::JournalArticle)
+        );
+        assert_eq!(reference.title(), Some("Fallback title"));
+        assert_eq!(reference.author_names(), ["Marx, Karl", "Lindgren, Astrid"]);
+        assert_eq!(reference.authors()[1].family.as_deref(), Some("Lindgren"));
+        assert_eq!(reference.year(), Some(1867));
+        assert_eq!(
+            reference
+                .publication_date()
+                .and_then(|date| date.to_iso8601())
+                .as_deref(),
+            Some("1867-09-14")
+        );
+        assert_eq!(reference.access_date(), None);
+        assert_eq!(reference.journal(), Some("Journal of Things"));
+        assert_eq!(reference.abstract_text(), Some("Abstract"));
+        assert_eq!(reference.keywords(), ["a", "b"]);
+        assert_eq!(reference.doi(), None);
+        assert_eq!(reference.record().len(), 10);
+    }
+
+    #[test]
+    fn test_preference() {
+        let reference = Reference::new(Record::from(vec![
+            (Tag::Title, "TI"),
+            (Tag::PrimaryTitle, "T1"),
+            (Tag::PrimaryDate, "2001"),
+            (Tag::PublicationYear, "2002///"),
+            (Tag::JournalAbbreviation, "JA"),
+            (Tag::JournalName, "JO"),
+            (Tag::AlternateAbstract, "N2"),
+            (Tag::Abstract, "AB"),
+        ]));
+        assert_eq!(reference.title(), Some("T1"));
+        assert_eq!(reference.year(), Some(2002));
+        assert_eq!(reference.journal(), Some("JO"));
+        assert_eq!(reference.abstract_text(), Some("AB"));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let mut record = Record::from(vec![
+            (Tag::AccessionNumber, "618012345"),
+            (Tag::IsbnIssn, "1932-6208"),
+        ]);
+        assert_eq!(
+            Reference::new(record.clone()).identifiers(),
+            [Identifier::InvalidIssn("1932-6208".into())]
+        );
+        record.fields.push((Tag::DatabaseName, "Ovid MEDLINE(R)"));
+        assert_eq!(
+            Reference::new(record).identifiers(),
+            [
+                Identifier::Pmid("618012345".into()),
+                Identifier::InvalidIssn("1932-6208".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_year() {
+        let reference = Reference::new(Record::from(vec![(Tag::PublicationYear, "n.d.")]));
+        assert_eq!(reference.year(), None);
+        assert_eq!(Reference::default().year(), None);
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ValueError;
+
+macro_rules! reference_types {
+    ($($variant:ident => $code:literal, $label:literal;)*) => {
+        /// The type of a reference, the value of the `TY` tag.
+        ///
+        /// Every type of the RIS specification has its own variant, all other codes
+        /// are `ReferenceType::Other`.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum ReferenceType {
+            $(#[doc = $label] $variant,)*
+            /// A type that is not part of the RIS specification, holding its code.
+            Other(String),
+        }
+
+        impl ReferenceType {
+            /// The type with the given code, `ReferenceType::Other` if it is not a
+            /// type of the specification.
+            pub fn from_code(code: &str) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    _ => Self::Other(code.to_owned()),
+                }
+            }
+
+            /// The code of the type, such as `JOUR`.
+            pub fn code(&self) -> &str {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Other(code) => code,
+                }
+            }
+
+            /// A human-readable label, such as `Journal article`.
+            pub fn label(&self) -> &str {
+                match self {
+                    $(Self::$variant => $label,)*
+                    Self::Other(_) => "Other",
+                }
+            }
+        }
+    };
+}
+
+reference_types! {
+    Abstract => "ABST", "Abstract";
+    AudiovisualMaterial => "ADVS", "Audiovisual material";
+    AggregatedDatabase => "AGGR", "Aggregated database";
+    AncientText => "ANCIENT", "Ancient text";
+    ArtWork => "ART", "Art work";
+    Bill => "BILL", "Bill";
+    Blog => "BLOG", "Blog";
+    Book => "BOOK", "Whole book";
+    Case => "CASE", "Case";
+    BookChapter => "CHAP", "Book chapter";
+    Chart => "CHART", "Chart";
+    ClassicalWork => "CLSWK", "Classical work";
+    ComputerProgram => "COMP", "Computer program";
+    ConferenceProceedings => "CONF", "Conference proceedings";
+    ConferencePaper => "CPAPER", "Conference paper";
+    Catalog => "CTLG", "Catalog";
+    DataFile => "DATA", "Data file";
+    OnlineDatabase => "DBASE", "Online database";
+    Dictionary => "DICT", "Dictionary";
+    ElectronicBook => "EBOOK", "Electronic book";
+    ElectronicBookSection => "ECHAP", "Electronic book section";
+    EditedBook => "EDBOOK", "Edited book";
+    ElectronicArticle => "EJOUR", "Electronic article";
+    WebPage => "ELEC", "Web page";
+    Encyclopedia => "ENCYC", "Encyclopedia";
+    Equation => "EQUA", "Equation";
+    Figure => "FIGURE", "Figure";
+    Generic => "GEN", "Generic";
+    GovernmentDocument => "GOVDOC", "Government document";
+    Grant => "GRANT", "Grant";
+    Hearing => "HEAR", "Hearing";
+    InternetCommunication => "ICOMM", "Internet communication";
+    InPress => "INPR", "In press";
+    FullJournal => "JFULL", "Journal (full)";
+    JournalArticle => "JOUR", "Journal article";
+    LegalRule => "LEGAL", "Legal rule or regulation";
+    Manuscript => "MANSCPT", "Manuscript";
+    Map => "MAP", "Map";
+    MagazineArticle => "MGZN", "Magazine article";
+    MotionPicture => "MPCT", "Motion picture";
+    OnlineMultimedia => "MULTI", "Online multimedia";
+    MusicScore => "MUSIC", "Music score";
+    NewspaperArticle => "NEWS", "Newspaper article";
+    Pamphlet => "PAMP", "Pamphlet";
+    Patent => "PAT", "Patent";
+    PersonalCommunication => "PCOMM", "Personal communication";
+    Report => "RPRT", "Report";
+    SerialPublication => "SER", "Serial publication";
+    Slide => "SLIDE", "Slide";
+    SoundRecording => "SOUND", "Sound recording";
+    Standard => "STAND", "Standard";
+    Statute => "STAT", "Statute";
+    Thesis => "THES", "Thesis or dissertation";
+    UnenactedBill => "UNBILL", "Unenacted bill";
+    UnpublishedWork => "UNPB", "Unpublished work";
+    VideoRecording => "VIDEO", "Video recording";
+}
+
+impl ReferenceType {
+    /// Whether the type is part of the RIS specification.
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Other(_))
+    }
+}
+
+impl FromStr for ReferenceType {
+    type Err = ValueError;
+
+    /// Parse a type from its code, surrounding whitespace is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim();
+        if code.is_empty() || !code.bytes().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(ValueError::new(format!(
+                "reference type should be letters or digits, not {:?}",
+                s
+            )));
+        }
+        Ok(Self::from_code(code))
+    }
+}
+
+impl fmt::Display for ReferenceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "JOUR".parse::<ReferenceType>().unwrap(),
+            ReferenceType::JournalArticle
+        );
+        assert_eq!(
+            " CHAP ".parse::<ReferenceType>().unwrap(),
+            ReferenceType::BookChapter
+        );
+        let other = "WEIRD".parse::<ReferenceType>().unwrap();
+        assert_eq!(other, ReferenceType::Other("WEIRD".into()));
+        assert!(!other.is_known());
+        assert!("".parse::<ReferenceType>().is_err());
+        assert_eq!(
+            "JO UR".parse::<ReferenceType>().unwrap_err().message(),
+            "reference type should be letters or digits, not \"JO UR\""
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(ReferenceType::Thesis.code(), "THES");
+        assert_eq!(ReferenceType::Thesis.label(), "Thesis or dissertation");
+        assert_eq!(ReferenceType::VideoRecording.to_string(), "VIDEO");
+        assert_eq!(
+            ReferenceType::from_code(ReferenceType::Abstract.code()),
+            ReferenceType::Abstract
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Location;
+use crate::utils::parse_utf8;
+use crate::{Error, ValueError};
+
+macro_rules! tags {
+    ($($variant:ident => $code:literal, $name:literal, $description:literal;)*) => {
+        /// A RIS tag, identified by its code without the separator, such as `TY`.
+        ///
+        /// Every tag allowed by `RisParser::default()` has its own variant, all other
+        /// tags are `Tag::Other`.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum Tag {
+            $(#[doc = $description] $variant,)*
+            /// A tag that is not part of the standard set, holding its code.
+            Other(String),
+        }
+
+        impl Tag {
+            /// The tag with the given code, `Tag::Other` if it is not a standard tag.
+            ///
+            /// The code is not validated, use `FromStr` for untrusted input.
+            pub fn from_code(code: &str) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    _ => Self::Other(code.to_owned()),
+                }
+            }
+
+            /// The code of the tag, such as `TY`.
+            pub fn code(&self) -> &str {
+                match self {
+                    $(Self::$variant => $code,)*
+                    Self::Other(code) => code,
+                }
+            }
+
+            /// A short human-readable name, such as `Type of reference`.
+            pub fn name(&self) -> &str {
+                match self {
+                    $(Self::$variant => $name,)*
+                    Self::Other(_) => "Other",
+                }
+            }
+
+            /// A description of what the field holds.
+            pub fn description(&self) -> &str {
+                match self {
+                    $(Self::$variant => $description,)*
+                    Self::Other(_) => "A tag that is not part of the standard RIS tags.",
+                }
+            }
+        }
+    };
+}
+
+tags! {
+    Type => "TY", "Type of reference", "The type of the reference, such as `JOUR`. Must be the first tag of a reference.";
+    PrimaryAuthor => "A1", "Primary author", "A primary author, in `Last, First` form.";
+    SecondaryAuthor => "A2", "Secondary author", "A secondary author, such as an editor.";
+    TertiaryAuthor => "A3", "Tertiary author", "A tertiary author, such as a series editor.";
+    SubsidiaryAuthor => "A4", "Subsidiary author", "A subsidiary author, such as a translator.";
+    Abstract => "AB", "Abstract", "The abstract.";
+    AuthorAddress => "AD", "Author address", "The address or affiliation of the authors.";
+    AccessionNumber => "AN", "Accession number", "The accession number in the database the reference was exported from.";
+    Author => "AU", "Author", "An author, in `Last, First` form.";
+    Custom1 => "C1", "Custom 1", "Custom field 1, its meaning depends on the type of reference.";
+    Custom2 => "C2", "Custom 2", "Custom field 2, its meaning depends on the type of reference.";
+    Custom3 => "C3", "Custom 3", "Custom field 3, its meaning depends on the type of reference.";
+    Custom4 => "C4", "Custom 4", "Custom field 4, its meaning depends on the type of reference.";
+    Custom5 => "C5", "Custom 5", "Custom field 5, its meaning depends on the type of reference.";
+    Custom6 => "C6", "Custom 6", "Custom field 6, its meaning depends on the type of reference.";
+    Custom7 => "C7", "Custom 7", "Custom field 7, its meaning depends on the type of reference.";
+    Custom8 => "C8", "Custom 8", "Custom field 8, its meaning depends on the type of reference.";
+    Caption => "CA", "Caption", "The caption of a figure or table.";
+    CallNumber => "CN", "Call number", "The call number in a library.";
+    PlacePublished => "CY", "Place published", "The city or place of publication.";
+    Date => "DA", "Date", "A date related to the reference, in `YYYY/MM/DD/other` form.";
+    DatabaseName => "DB", "Name of database", "The name of the database the reference was exported from.";
+    Doi => "DO", "DOI", "The digital object identifier.";
+    DatabaseProvider => "DP", "Database provider", "The provider of the database the reference was exported from.";
+    Edition => "ET", "Edition", "The edition.";
+    EndPage => "EP", "End page", "The last page.";
+    ReferenceId => "ID", "Reference ID", "An identifier of the reference within the file.";
+    Issue => "IS", "Issue number", "The issue number.";
+    AlternateTitle => "J2", "Alternate title", "An alternate title, such as an abbreviated journal name.";
+    JournalAbbreviation => "JA", "Journal abbreviation", "The standard abbreviation of the journal name.";
+    JournalFullName => "JF", "Journal full name", "The full name of the journal.";
+    JournalName => "JO", "Journal name", "The name of the journal, often abbreviated.";
+    Keyword => "KW", "Keyword", "A keyword.";
+    FileAttachment => "L1", "File attachment", "A link to a file attached to the reference, such as a PDF.";
+    FullTextLink => "L2", "Full-text link", "A link to the full text.";
+    RelatedRecords => "L3", "Related records", "A link to related records, often a DOI URL.";
+    Figure => "L4", "Figure", "A link to an image.";
+    Language => "LA", "Language", "The language of the work.";
+    Label => "LB", "Label", "A label for the reference.";
+    Miscellaneous1 => "M1", "Miscellaneous 1", "Miscellaneous field 1, often a number.";
+    TypeOfWork => "M3", "Type of work", "The type of work, such as `Journal Article`.";
+    Notes => "N1", "Notes", "Notes on the reference.";
+    AlternateAbstract => "N2", "Abstract (alternate)", "The abstract, as written by some exporters instead of `AB`.";
+    NumberOfVolumes => "NV", "Number of volumes", "The number of volumes.";
+    OriginalPublication => "OP", "Original publication", "Details of the original publication.";
+    Publisher => "PB", "Publisher", "The publisher.";
+    PublicationYear => "PY", "Publication year", "The year of publication.";
+    ReviewedItem => "RI", "Reviewed item", "The work that is reviewed.";
+    ResearchNotes => "RN", "Research notes", "Notes of the researcher on the reference.";
+    ReprintEdition => "RP", "Reprint edition", "The reprint status, such as `IN FILE`.";
+    Section => "SE", "Section", "The section of the work.";
+    IsbnIssn => "SN", "ISBN/ISSN", "The ISBN or ISSN.";
+    StartPage => "SP", "Start page", "The first page, or the page range.";
+    ShortTitle => "ST", "Short title", "A short form of the title.";
+    PrimaryTitle => "T1", "Primary title", "The primary title.";
+    SecondaryTitle => "T2", "Secondary title", "The secondary title, such as the journal or book title.";
+    TertiaryTitle => "T3", "Tertiary title", "The tertiary title, such as the series title.";
+    TranslatedAuthor => "TA", "Translated author", "The translated name of an author.";
+    Title => "TI", "Title", "The title.";
+    TranslatedTitle => "TT", "Translated title", "The translated title.";
+    Url => "UR", "URL", "A web address of the reference.";
+    Volume => "VL", "Volume", "The volume number.";
+    PrimaryDate => "Y1", "Primary date", "The primary date, in `YYYY/MM/DD/other` form.";
+    AccessDate => "Y2", "Access date", "The date the work was accessed.";
+    Unknown => "UK", "Unknown", "A field of unspecified meaning that some exporters write.";
+    EndOfReference => "ER", "End of reference", "The end of the reference. Must be the last tag of a reference.";
+}
+
+impl FromStr for Tag {
+    type Err = ValueError;
+
+    /// Parse a tag from its code, such as `TY`, or from the tag as it is written
+    /// in RIS, such as `TY  - `.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim_end_matches([' ', '\t', '-']);
+        if code.is_empty() || !code.bytes().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(ValueError::new(format!(
+                "tag should be letters or digits, not {:?}",
+                s
+            )));
+        }
+        Ok(Self::from_code(code))
+    }
+}
+
+impl TryFrom<&[u8]> for Tag {
+    type Error = Error;
+
+    /// Parse a tag as it is read from the input. The error has no location yet, the
+    /// parser sets it.
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        parse_utf8(value)?
+            .parse()
+            .map_err(|e: ValueError| Error::UnknownTag(e.to_string(), Location::default()))
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::DEFAULT_TAGS;
+
+    #[test]
+    fn test_default_tags() {
+        for tag in DEFAULT_TAGS {
+            let parsed = Tag::try_from(&tag[..]).unwrap();
+            assert!(!matches!(parsed, Tag::Other(_)), "{:?}", parsed);
+            assert_eq!(format!("{}  - ", parsed).as_bytes(), tag);
+            assert_eq!(parsed.code().parse::<Tag>().unwrap(), parsed);
+        }
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("TY".parse::<Tag>().unwrap(), Tag::Type);
+        assert_eq!("ER  - ".parse::<Tag>().unwrap(), Tag::EndOfReference);
+        assert_eq!("ER  -".parse::<Tag>().unwrap(), Tag::EndOfReference);
+        assert_eq!("ZZ  - ".parse::<Tag>().unwrap(), Tag::Other("ZZ".into()));
+        assert_eq!("FAU - ".parse::<Tag>().unwrap(), Tag::Other("FAU".into()));
+        assert!("".parse::<Tag>().is_err());
+        assert!("T Y".parse::<Tag>().is_err());
+        assert!(Tag::try_from(&b"\xff\xfe"[..]).is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Tag::Doi.to_string(), "DO");
+        assert_eq!(Tag::Doi.name(), "DOI");
+        assert_eq!(Tag::Other("ZZ".into()).to_string(), "ZZ");
+        assert_eq!(Tag::Other("ZZ".into()).name(), "Other");
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::Location;
+use crate::Error;
+use crate::Handler;
+use crate::PResult;
+use crate::Tag;
+use crate::TagSet;
+
+/// Handler that keeps the fields of a reference in input order, keyed by `Tag`.
+#[derive(Debug, Clone)]
+pub struct TagHandler<'a, 'b> {
+    start_tag: &'a [u8],
+    end_tag: &'a [u8],
+    allowed_tags: &'a TagSet,
+    fields: Vec<(Tag, &'b str)>,
+}
+
+impl<'a, 'b> TagHandler<'a, 'b> {
+    pub fn new(start_tag: &'a [u8], end_tag: &'a [u8], allowed_tags: &'a TagSet) -> Self {
+        Self {
+            start_tag,
+            end_tag,
+            allowed_tags,
+            fields: Vec::with_capacity(20),
+        }
+    }
+}
+
+impl<'a, 'b> Handler<'a, 'b, &'b str, Vec<(Tag, &'b str)>> for TagHandler<'a, 'b> {
+    fn handle(&mut self, tag: &'b [u8], content: &'b str) -> PResult<()> {
+        if !self.allowed_tags.contains(tag) {
+            return Err(Error::UnknownTag(
+                "tag should be in allowed tags".into(),
+                Location::default(),
+            ));
+        }
+        if tag != self.end_tag {
+            self.fields.push((Tag::try_from(tag)?, content));
+        }
+        Ok(())
+    }
+
+    fn handle_unknown(&mut self, tag: &'b [u8], content: &'b str) -> PResult<()> {
+        self.fields.push((Tag::try_from(tag)?, content));
+        Ok(())
+    }
+
+    fn finish(self) -> Vec<(Tag, &'b str)> {
+        self.fields
+    }
+
+    fn start_tag(&self) -> &'a [u8] {
+        self.start_tag
+    }
+
+    fn end_tag(&self) -> &'a [u8] {
+        self.end_tag
+    }
+
+    fn allowed_tags(&self) -> &'a TagSet {
+        self.allowed_tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_handler() {
+        let allowed_tags = TagSet::from_iter([b"TY  - ", b"AU  - ", b"ER  - "]);
+        let mut handler = TagHandler::new(b"TY  - ", b"ER  - ", &allowed_tags);
+
+        handler.handle(b"TY  - ", "JOUR").unwrap();
+        handler.handle(b"AU  - ", "Marx, Karl").unwrap();
+        handler.handle_unknown(b"ZZ  - ", "custom").unwrap();
+        handler.handle(b"AU  - ", "Lindgren, Astrid").unwrap();
+        handler.handle(b"ER  - ", "").unwrap();
+        assert!(handler.handle(b"KW  - ", "not allowed").is_err());
+
+        assert_eq!(
+            handler.finish(),
+            [
+                (Tag::Type, "JOUR"),
+                (Tag::Author, "Marx, Karl"),
+                (Tag::Other("ZZ".into()), "custom"),
+                (Tag::Author, "Lindgren, Astrid"),
+            ]
+        );
+    }
+}
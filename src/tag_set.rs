@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+/// A set of tags, which may differ in width.
+///
+/// Finding the tag at the start of a line tries every width in the set, longest
+/// first. A set in which all tags have the same width, like the RIS tags, therefore
+/// takes a single hash lookup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagSet {
+    tags: HashSet<Box<[u8]>>,
+    /// The distinct widths of the tags, in descending order.
+    widths: Vec<usize>,
+}
+
+impl TagSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tag, returns whether it was not in the set yet.
+    pub fn insert(&mut self, tag: impl AsRef<[u8]>) -> bool {
+        let tag = tag.as_ref();
+        if let Err(idx) = self.widths.binary_search_by(|w| tag.len().cmp(w)) {
+            self.widths.insert(idx, tag.len());
+        }
+        self.tags.insert(tag.into())
+    }
+
+    pub fn contains(&self, tag: &[u8]) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.tags.iter().map(|tag| &tag[..])
+    }
+
+    /// The longest tag in the set that `text` starts with.
+    pub fn find_prefix<'t>(&self, text: &'t [u8]) -> Option<&'t [u8]> {
+        self.widths
+            .iter()
+            .filter_map(|width| text.get(..*width))
+            .find(|tag| self.tags.contains(*tag))
+    }
+
+    /// Width of the narrowest tag in the set, 0 if it is empty.
+    pub fn min_width(&self) -> usize {
+        self.widths.last().copied().unwrap_or(0)
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for TagSet {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for tag in iter {
+            self.insert(tag);
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> FromIterator<T> for TagSet {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tags = Self::new();
+        tags.extend(iter);
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_prefix() {
+        let tags: TagSet = ["AU  - ", "FAU - ", "PMID- ", "TI - ", "ABSTRACT: "]
+            .into_iter()
+            .collect();
+        assert_eq!(tags.widths, [10, 6, 5]);
+        assert_eq!(tags.min_width(), 5);
+        assert_eq!(tags.find_prefix(b"FAU - Marx"), Some(&b"FAU - "[..]));
+        assert_eq!(tags.find_prefix(b"TI - Title"), Some(&b"TI - "[..]));
+        assert_eq!(
+            tags.find_prefix(b"ABSTRACT: text"),
+            Some(&b"ABSTRACT: "[..])
+        );
+        assert_eq!(tags.find_prefix(b"AU - "), None);
+        assert_eq!(tags.find_prefix(b"TI"), None);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut tags = TagSet::new();
+        assert!(tags.insert("TY  - "));
+        assert!(!tags.insert(b"TY  - "));
+        assert!(tags.insert(String::from("ER  - ")));
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags.widths, [6]);
+        assert!(tags.contains(b"ER  - "));
+    }
+}
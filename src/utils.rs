@@ -1,6 +1,26 @@
+use crate::error::Location;
 use crate::Error;
 use crate::PResult;
 
 pub fn parse_utf8(a: &[u8]) -> PResult<&str> {
-    std::str::from_utf8(a).map_err(|_| Error::ParserError(format!("invalid utf-8 in tag {:?}", a)))
+    std::str::from_utf8(a).map_err(|e| Error::InvalidUtf8(e, Location::default()))
+}
+
+/// Remove a single trailing line ending (`\r\n`, `\n` or `\r`) from the slice.
+pub fn trim_line_ending(a: &[u8]) -> &[u8] {
+    match a {
+        [rest @ .., b'\r', b'\n'] => rest,
+        [rest @ .., b'\n'] | [rest @ .., b'\r'] => rest,
+        _ => a,
+    }
+}
+
+/// Remove every trailing line ending from the slice, including those of empty lines.
+pub fn trim_line_endings(a: &[u8]) -> &[u8] {
+    let len = a.len()
+        - a.iter()
+            .rev()
+            .take_while(|c| **c == b'\n' || **c == b'\r')
+            .count();
+    &a[..len]
 }
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::builder::DEFAULT_TAGS;
+use crate::recognizer::recognize;
+use crate::{ListOrItem, Record, Reference, Tag};
+
+/// The line ending written by `RisWriter`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Self::Lf => b"\n",
+            Self::CrLf => b"\r\n",
+        }
+    }
+}
+
+/// Writer of references in RIS form to an `io::Write`.
+///
+/// Every reference starts with its `TY` field and ends with `ER  - `, tags are
+/// written as `XX  - ` whatever their form in the input. The fields of maps are
+/// written in the tag order, which is the order of the tags in
+/// `RisParser::default()` unless set with `with_tag_order`; tags that are not in
+/// it come last, in alphabetical order. Records are written in their own order.
+///
+/// The lines of multi-line values are written with the line ending of the writer.
+/// A line of a value that `RisParser::default()` would read as a tag, such as
+/// `ER  - `, is indented by a space, so the value is read back with that space.
+/// Values returned by the parser never have such lines, they are written as they
+/// are.
+#[derive(Debug)]
+pub struct RisWriter<W: Write> {
+    writer: W,
+    line_ending: LineEnding,
+    bom: bool,
+    tag_order: Vec<Tag>,
+    /// Whether anything was written yet, the BOM goes before the first reference.
+    started: bool,
+}
+
+impl<W: Write> RisWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            line_ending: LineEnding::default(),
+            bom: false,
+            tag_order: DEFAULT_TAGS
+                .iter()
+                .filter_map(|tag| Tag::try_from(&tag[..]).ok())
+                .collect(),
+            started: false,
+        }
+    }
+
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Start the output with a UTF-8 byte order mark.
+    pub fn with_bom(mut self, bom: bool) -> Self {
+        self.bom = bom;
+        self
+    }
+
+    /// Set the order in which the fields of maps are written.
+    pub fn with_tag_order(mut self, tag_order: impl IntoIterator<Item = Tag>) -> Self {
+        self.tag_order = tag_order.into_iter().collect();
+        self
+    }
+
+    /// Write a reference as returned by `RisParser::parse`.
+    pub fn write_map(&mut self, reference: &HashMap<&str, &str>) -> io::Result<()> {
+        let fields = parse_tags(reference.iter().map(|(tag, content)| (*tag, *content)))?;
+        self.write_sorted(fields)
+    }
+
+    /// Write a reference as returned by `RisParser::parse_lists`.
+    pub fn write_lists(&mut self, reference: &HashMap<&str, ListOrItem<&str>>) -> io::Result<()> {
+        let fields = parse_tags(reference.iter().flat_map(|(tag, value)| {
+            let values = match value {
+                ListOrItem::List(values) => values.as_slice(),
+                ListOrItem::Item(value) => std::slice::from_ref(value),
+            };
+            values.iter().map(move |content| (*tag, *content))
+        }))?;
+        self.write_sorted(fields)
+    }
+
+    /// Write a reference as returned by `RisParser::parse_records`, in the order
+    /// of its fields.
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.write_fields(
+            record
+                .iter()
+                .map(|(tag, content)| (tag.clone(), content))
+                .collect(),
+        )
+    }
+
+    /// Write a reference as returned by `RisParser::parse_typed`.
+    pub fn write_reference(&mut self, reference: &Reference) -> io::Result<()> {
+        self.write_record(reference.record())
+    }
+
+    /// Write text outside of the references as is, such as a `Preamble`.
+    pub fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.start()?;
+        self.writer.write_all(text.as_bytes())
+    }
+
+    /// Flush the output and return the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_sorted(&mut self, mut fields: Vec<(Tag, &str)>) -> io::Result<()> {
+        // Sorting is stable, so the values of a tag stay in order.
+        fields.sort_by_cached_key(|(tag, _)| {
+            match self.tag_order.iter().position(|ordered| ordered == tag) {
+                Some(idx) => (idx, String::new()),
+                None => (self.tag_order.len(), tag.code().to_owned()),
+            }
+        });
+        self.write_fields(fields)
+    }
+
+    fn write_fields(&mut self, mut fields: Vec<(Tag, &str)>) -> io::Result<()> {
+        let Some(type_idx) = fields.iter().position(|(tag, _)| *tag == Tag::Type) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "reference should have a TY field",
+            ));
+        };
+        let type_field = fields.remove(type_idx);
+        self.start()?;
+        self.write_field(&Tag::Type, type_field.1)?;
+        for (tag, content) in &fields {
+            if *tag != Tag::Type && *tag != Tag::EndOfReference {
+                self.write_field(tag, content)?;
+            }
+        }
+        self.write_field(&Tag::EndOfReference, "")
+    }
+
+    /// Write the BOM before the first output.
+    fn start(&mut self) -> io::Result<()> {
+        if self.bom && !self.started {
+            self.writer.write_all(b"\xef\xbb\xbf")?;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    fn write_field(&mut self, tag: &Tag, content: &str) -> io::Result<()> {
+        let line_ending = self.line_ending.as_bytes();
+        write!(self.writer, "{}  - ", tag)?;
+        for (idx, line) in lines(content).enumerate() {
+            if idx > 0 {
+                self.writer.write_all(line_ending)?;
+                if recognize(line.as_bytes(), false).is_some() {
+                    self.writer.write_all(b" ")?;
+                }
+            }
+            self.writer.write_all(line.as_bytes())?;
+        }
+        self.writer.write_all(line_ending)
+    }
+}
+
+/// The tags of the fields, as written in the input, as `Tag`s.
+fn parse_tags<'c>(
+    fields: impl Iterator<Item = (&'c str, &'c str)>,
+) -> io::Result<Vec<(Tag, &'c str)>> {
+    fields
+        .map(|(tag, content)| {
+            let tag = tag
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            Ok((tag, content))
+        })
+        .collect()
+}
+
+/// The lines of `s`, which may end in `\n`, `\r\n` or `\r`.
+fn lines(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(s);
+    std::iter::from_fn(move || {
+        let s = rest?;
+        match s.find(['\r', '\n']) {
+            Some(idx) => {
+                let skip = if s[idx..].starts_with("\r\n") { 2 } else { 1 };
+                rest = Some(&s[idx + skip..]);
+                Some(&s[..idx])
+            }
+            None => {
+                rest = None;
+                Some(s)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RisParser;
+
+    const INPUT: &[u8] = b"TY  - JOUR
+AU  - Marx, Karl
+T1  - Title
+AU  - Lindgren, Astrid
+AB  - First line
+second line
+ZZ  - custom
+KW  - 
+ER  - 
+
+TY  - BOOK
+ID  - 2
+N2  - doses of
+10  - 20 mg
+is - not clear
+ty  - lowercase
+ER  - 
+";
+
+    fn write(write: impl FnOnce(&mut RisWriter<Vec<u8>>) -> io::Result<()>) -> Vec<u8> {
+        let mut writer = RisWriter::new(Vec::new());
+        write(&mut writer).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let parser = RisParser::default();
+        let references = parser.parse(INPUT).unwrap();
+        let output = write(|writer| references.iter().try_for_each(|r| writer.write_map(r)));
+        assert_eq!(parser.parse(&output).unwrap(), references);
+
+        let references = parser.parse_lists(INPUT).unwrap();
+        let output = write(|writer| references.iter().try_for_each(|r| writer.write_lists(r)));
+        assert_eq!(parser.parse_lists(&output).unwrap(), references);
+
+        let records = parser.parse_records(INPUT).unwrap();
+        let output = write(|writer| records.iter().try_for_each(|r| writer.write_record(r)));
+        assert_eq!(parser.parse_records(&output).unwrap(), records);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            std::str::from_utf8(INPUT)
+                .unwrap()
+                .replace("ER  - \n\n", "ER  - \n")
+        );
+    }
+
+    #[test]
+    fn test_tag_order() {
+        let parser = RisParser::default();
+        let reference = &parser.parse(INPUT).unwrap()[0];
+        let output = write(|writer| writer.write_map(reference));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "TY  - JOUR\nAB  - First line\nsecond line\nAU  - Lindgren, Astrid\nKW  - \n\
+T1  - Title\nZZ  - custom\nER  - \n"
+        );
+
+        let mut writer =
+            RisWriter::new(Vec::new()).with_tag_order([Tag::PrimaryTitle, Tag::Author]);
+        writer.write_map(reference).unwrap();
+        let output = writer.finish().unwrap();
+        assert!(output.starts_with(b"TY  - JOUR\nT1  - Title\nAU  - Lindgren, Astrid\nAB  - "));
+    }
+
+    #[test]
+    fn test_line_ending_and_bom() {
+        let reference = HashMap::from([("TY  - ", "JOUR"), ("N1  - ", "a\nb\r\nc\rd")]);
+        let mut writer = RisWriter::new(Vec::new())
+            .with_line_ending(LineEnding::CrLf)
+            .with_bom(true);
+        writer.write_map(&reference).unwrap();
+        writer.write_map(&reference).unwrap();
+        let output = writer.finish().unwrap();
+        let reference = b"TY  - JOUR\r\nN1  - a\r\nb\r\nc\r\nd\r\nER  - \r\n";
+        assert_eq!(
+            output,
+            [&b"\xef\xbb\xbf"[..], reference, reference].concat()
+        );
+        assert_eq!(RisParser::default().parse(&output).unwrap().len(), 2);
+
+        let writer = RisWriter::new(Vec::new()).with_bom(true);
+        assert_eq!(writer.finish().unwrap(), b"\xef\xbb\xbf");
+    }
+
+    #[test]
+    fn test_multi_line_safety() {
+        let reference = HashMap::from([
+            ("TY  - ", "JOUR"),
+            ("AB  - ", "text\nER  - \nTI  - not a tag\n"),
+        ]);
+        let output = write(|writer| writer.write_map(&reference));
+        assert_eq!(
+            output,
+            b"TY  - JOUR\nAB  - text\n ER  - \n TI  - not a tag\n\nER  - \n"
+        );
+        let parsed = RisParser::default().parse(&output).unwrap();
+        assert_eq!(parsed.len(), 1);
+        // The escaped lines are read back with their space.
+        assert_eq!(parsed[0]["AB  - "], "text\n ER  - \n TI  - not a tag\n");
+
+        // Lines that the parser reads as content are not escaped.
+        let reference = HashMap::from([("TY  - ", "JOUR"), ("AB  - ", "a\nis - b\n10  - c")]);
+        let output = write(|writer| writer.write_map(&reference));
+        assert_eq!(output, b"TY  - JOUR\nAB  - a\nis - b\n10  - c\nER  - \n");
+        assert_eq!(RisParser::default().parse(&output).unwrap(), [reference]);
+    }
+
+    #[test]
+    fn test_missing_type() {
+        let mut writer = RisWriter::new(Vec::new());
+        let err = writer
+            .write_map(&HashMap::from([("T1  - ", "Title")]))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = writer.write_map(&HashMap::from([("T 1", "")])).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(writer.finish().unwrap().is_empty());
+    }
+}
@@ -19,3 +19,16 @@ fn parse_handwritten() {
 
     assert_eq!(output.len(), num_lines);
 }
+
+#[test]
+fn parse_file() {
+    let ris_file_path = "benches/files/Appenzeller-Herzog_2019.ris";
+
+    let contents = fs::read(ris_file_path).unwrap();
+    let parser = RisParser::default();
+    let expected = parser.parse(&contents).unwrap();
+    // SAFETY: the file is not modified while the test runs.
+    let output = unsafe { parser.parse_file(ris_file_path) }.unwrap();
+
+    assert_eq!(output.references(), expected);
+}